@@ -91,6 +91,21 @@ fn main() {
         }).collect();
 
 
+    // draw the tree and paths if a file is given
+    if let Some(svg_file) = std::env::args().nth(1) {
+        let mut svg = rrt::SvgRenderer::new(explore_area);
+        svg.explore_area(&explore_area);
+        for o in [&o0, &o1, &o2, &o3] {
+            svg.obstacle(o);
+        }
+        svg.tree(&rrt.node_tree);
+        svg.path(&path_res);
+        svg.smooth_path(&path);
+        svg.start(&start);
+        svg.goal(&goal);
+        svg.save(svg_file).expect("cannot write the svg");
+    }
+
     let ret = RRTReturn {
         tree: node_tree,
         smooth_path: path,
@@ -0,0 +1,34 @@
+/// Plan in a scene file, optionally drawing the result, e.g.
+/// cargo run --example scene_planning -- examples/scenes/rrt_planning.toml plan.svg
+use rrt_merci::path::Path2D;
+use rrt_merci::{Scene, SvgRenderer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct SceneReturn {
+    path: Path2D,
+    smooth_path: Path2D,
+}
+
+fn main() {
+    let file = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "examples/scenes/rrt_planning.toml".to_string());
+    let scene = Scene::load(&file).expect("cannot load the scene");
+    let world = scene.build_world().expect("cannot build the obstacles");
+    let mut planner = scene.planner(&world).expect("cannot build the planner");
+
+    let path = planner.plan().expect("path not found!");
+    let smooth_path = path.path_smoothing_obstacle(planner.obstacles(), 1000);
+
+    if let Some(svg_file) = std::env::args().nth(2) {
+        let mut svg = SvgRenderer::new(scene.explore_area);
+        svg.scene(&scene);
+        svg.path(&path);
+        svg.smooth_path(&smooth_path);
+        svg.save(svg_file).expect("cannot write the svg");
+    }
+
+    let ret = SceneReturn { path, smooth_path };
+    println!("{}", serde_json::json!(ret));
+}
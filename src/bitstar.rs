@@ -0,0 +1,444 @@
+/// Batch Informed Trees (BIT*)
+use crate::bound::*;
+use crate::footprint::Footprint;
+use crate::informedrrtstar::sample_informed;
+use crate::math::*;
+use crate::path::Path2D;
+use crate::planner::Planner;
+use kiddo::{distance::squared_euclidean, KdTree};
+use crate::sampler::{Sampler, UniformSampler};
+use rand::{rngs::StdRng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// a state of the implicit graph, either a tree vertex or an unconnected sample
+#[derive(Debug, Clone)]
+struct BITState {
+    point: Point2D,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// cost-to-come through the tree, infinite for samples
+    cost: f32,
+    in_tree: bool,
+    /// vertex that was already in the tree when the batch started
+    old: bool,
+    pruned: bool,
+}
+
+impl BITState {
+    fn new(point: Point2D) -> Self {
+        Self {
+            point,
+            parent: None,
+            children: Vec::new(),
+            cost: f32::INFINITY,
+            in_tree: false,
+            old: false,
+            pruned: false,
+        }
+    }
+}
+
+/// edge queue entry, ordered so the BinaryHeap pops the lowest estimated solution cost
+struct EdgeItem {
+    key: f32,
+    from: usize,
+    to: usize,
+}
+
+impl PartialEq for EdgeItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for EdgeItem {}
+
+impl PartialOrd for EdgeItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EdgeItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.total_cmp(&self.key)
+    }
+}
+
+/// BIT* Configuration Object
+/// --
+///
+/// every batch adds `batch_size` samples (from the informed ellipse once a solution is known)
+/// to an implicit random geometric graph, which is searched in order of estimated solution cost
+/// through an edge queue. Edges are only checked for collision once they are popped from the
+/// queue, and states that can't improve the current solution are pruned before each batch.
+///
+/// Moving obstacles aren't checked, the search only avoids the static `obstacles`.
+pub struct BITStar<'a> {
+    pub start: Point2D,
+    pub goal: Point2D,
+    pub obstacles: Vec<&'a dyn Collision>,
+    /// robot shape checked against the obstacles, a point by default
+    pub footprint: Footprint,
+    pub explore_area: RectangleBounds,
+    pub batch_size: usize,
+    pub max_batches: usize,
+    /// scale of the connection radius, which shrinks with the number of states
+    pub connect_circle_dist: f32,
+    /// best path (from goal to start) after every batch, None while no solution is known
+    pub batch_paths: Vec<Option<Path2D>>,
+    states: Vec<BITState>,
+    /// samples until a solution is known (uniform over the explore area by default)
+    pub sampler: Box<dyn Sampler + 'a>,
+    kd_tree: KdTree<f32, usize, 2>,
+    rng: StdRng,
+}
+
+const START: usize = 0;
+const GOAL: usize = 1;
+
+impl Planner<'_> for BITStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        &self.obstacles
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.footprint
+    }
+
+    /// run max_batches batches and return the best path found
+    fn plan(&mut self) -> Option<Path2D> {
+        if self.is_collision(&self.start) || self.is_collision(&self.goal) {
+            return None;
+        }
+        self.states = vec![BITState::new(self.start), BITState::new(self.goal)];
+        self.states[START].cost = 0.0;
+        self.states[START].in_tree = true;
+        self.batch_paths.clear();
+
+        for _ in 0..self.max_batches {
+            self.run_batch();
+            self.batch_paths.push(self.best_path());
+        }
+        self.best_path()
+    }
+}
+
+impl<'a> BITStar<'a> {
+    /// create new planner, leave other parameters open
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        explore_area: RectangleBounds,
+        batch_size: usize,
+        max_batches: usize,
+        connect_circle_dist: f32,
+    ) -> Self {
+        Self {
+            start,
+            goal,
+            obstacles,
+            footprint: Footprint::Point,
+            explore_area,
+            batch_size,
+            max_batches,
+            connect_circle_dist,
+            batch_paths: Vec::new(),
+            states: Vec::new(),
+            sampler: Box::new(UniformSampler::new(explore_area)),
+            kd_tree: KdTree::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// seed the random source, identical inputs and seed give identical trees and paths
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// cost of the current solution, infinite while there is none
+    pub fn best_cost(&self) -> f32 {
+        self.states.get(GOAL).map_or(f32::INFINITY, |s| s.cost)
+    }
+
+    /// current solution from goal to start
+    pub fn best_path(&self) -> Option<Path2D> {
+        if !self.best_cost().is_finite() {
+            return None;
+        }
+        let mut path = vec![self.states[GOAL].point];
+        let mut current = GOAL;
+        while let Some(parent) = self.states[current].parent {
+            path.push(self.states[parent].point);
+            current = parent;
+        }
+        Some(Path2D(path))
+    }
+
+    fn distance(&self, a: usize, b: usize) -> f32 {
+        euclidean_distance(&subtract(&self.states[a].point, &self.states[b].point))
+    }
+
+    /// admissible estimate of the cost from the start
+    fn g_hat(&self, id: usize) -> f32 {
+        self.distance(START, id)
+    }
+
+    /// admissible estimate of the cost to the goal
+    fn h_hat(&self, id: usize) -> f32 {
+        self.distance(id, GOAL)
+    }
+
+    /// process the edge queue of a new batch until nothing in it can improve the solution
+    fn run_batch(&mut self) {
+        self.prune();
+        self.sample();
+
+        // new batch, every vertex gets expanded again
+        let mut vertex_queue = Vec::new();
+        for (id, s) in self.states.iter_mut().enumerate() {
+            if s.in_tree {
+                s.old = true;
+                vertex_queue.push(id);
+            }
+        }
+        let n_states = self.states.iter().filter(|s| !s.pruned).count() as f32;
+        let r = self.connect_circle_dist * (n_states.ln() / n_states).sqrt();
+        let mut edge_queue = BinaryHeap::new();
+
+        loop {
+            // expand vertices while they could provide better edges than the queue has
+            while let Some((pos, v_key)) = vertex_queue
+                .iter()
+                .map(|id| self.states[*id].cost + self.h_hat(*id))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+            {
+                if edge_queue.peek().is_some_and(|e: &EdgeItem| e.key < v_key) {
+                    break;
+                }
+                let v = vertex_queue.swap_remove(pos);
+                self.expand_vertex(v, r, &mut edge_queue);
+            }
+
+            let EdgeItem { from, to, .. } = match edge_queue.pop() {
+                Some(e) => e,
+                None => return,
+            };
+            let c_best = self.best_cost();
+            let c_hat = self.distance(from, to);
+            let from_cost = self.states[from].cost;
+            // the edge can't help anymore (the target got cheaper or the solution better)
+            if from_cost + c_hat >= self.states[to].cost
+                || from_cost + c_hat + self.h_hat(to) >= c_best
+            {
+                continue;
+            }
+
+            // lazy collision check, only for edges that could improve the solution
+            if self.is_collision_segment(&self.states[from].point, &self.states[to].point) {
+                continue;
+            }
+            if !self.states[to].in_tree {
+                self.states[to].in_tree = true;
+                vertex_queue.push(to);
+            }
+            self.set_parent(to, from, from_cost + c_hat);
+        }
+    }
+
+    /// queue the edges from a vertex to the near states that could improve the solution
+    fn expand_vertex(&self, v: usize, r: f32, edge_queue: &mut BinaryHeap<EdgeItem>) {
+        let c_best = self.best_cost();
+        let g_hat = self.g_hat(v);
+        let cost = self.states[v].cost;
+        let pt = self.states[v].point;
+        let near = match self.kd_tree.within_unsorted(&[pt.0, pt.1], r * r, &squared_euclidean) {
+            Ok(s) => s.iter().map(|(_d, idx)| **idx).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        for x in near {
+            let state = &self.states[x];
+            if x == v || state.pruned {
+                continue;
+            }
+            let c_hat = self.distance(v, x);
+            if g_hat + c_hat + self.h_hat(x) >= c_best {
+                continue;
+            }
+            if !state.in_tree {
+                edge_queue.push(EdgeItem {
+                    key: cost + c_hat + self.h_hat(x),
+                    from: v,
+                    to: x,
+                });
+            } else if !self.states[v].old
+                && state.parent != Some(v)
+                && self.states[v].parent != Some(x)
+                && cost + c_hat < state.cost
+            {
+                // rewiring candidates, only from vertices new in this batch
+                edge_queue.push(EdgeItem {
+                    key: cost + c_hat + self.h_hat(x),
+                    from: v,
+                    to: x,
+                });
+            }
+        }
+    }
+
+    /// (re)attach a state below a new parent, the costs of its subtree follow
+    fn set_parent(&mut self, id: usize, parent: usize, cost: f32) {
+        if let Some(old_parent) = self.states[id].parent {
+            self.states[old_parent].children.retain(|c| *c != id);
+        }
+        self.states[id].parent = Some(parent);
+        self.states[parent].children.push(id);
+
+        let delta = cost - self.states[id].cost;
+        if !delta.is_finite() {
+            self.states[id].cost = cost;
+            return;
+        }
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            self.states[current].cost += delta;
+            stack.extend(self.states[current].children.iter().copied());
+        }
+    }
+
+    /// drop states that can't improve the solution
+    ///
+    /// vertices that could only do so through a different parent go back to the samples
+    fn prune(&mut self) {
+        let c_best = self.best_cost();
+        if !c_best.is_finite() {
+            return;
+        }
+        // the vertices of the solution sit on the bound, rounding must not cut them off
+        let mut solution = vec![GOAL];
+        while let Some(parent) = self.states[*solution.last().unwrap()].parent {
+            solution.push(parent);
+        }
+        for id in 0..self.states.len() {
+            if solution.contains(&id) || self.states[id].pruned {
+                continue;
+            }
+            let (g_hat, h_hat) = (self.g_hat(id), self.h_hat(id));
+            let state = &mut self.states[id];
+            // samples on the bound can't improve the solution, vertices on it may be part of one
+            let hopeless = if state.in_tree {
+                g_hat + h_hat > c_best
+            } else {
+                g_hat + h_hat >= c_best
+            };
+            if hopeless {
+                state.pruned = true;
+                state.in_tree = false;
+            } else if state.in_tree && state.cost + h_hat > c_best {
+                state.in_tree = false;
+            }
+        }
+
+        // vertices below a disconnected one are disconnected as well
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in 0..self.states.len() {
+                let parent_lost = self.states[id].parent.is_some_and(|p| !self.states[p].in_tree);
+                let state = &mut self.states[id];
+                if id != START && state.in_tree && (parent_lost || state.parent.is_none()) {
+                    state.in_tree = false;
+                    changed = true;
+                }
+            }
+        }
+        for state in self.states.iter_mut() {
+            state.children.clear();
+            if !state.in_tree {
+                state.parent = None;
+                state.cost = f32::INFINITY;
+                state.old = false;
+            }
+        }
+        for id in 0..self.states.len() {
+            if let Some(parent) = self.states[id].parent {
+                self.states[parent].children.push(id);
+            }
+        }
+    }
+
+    /// add a batch of collision free samples, from the informed set once a solution is known
+    fn sample(&mut self) {
+        let c_best = self.best_cost();
+        // give up eventually if the free space is (almost) empty
+        let max_attempts = 100 * self.batch_size;
+        let mut added = 0;
+        for _ in 0..max_attempts {
+            if added == self.batch_size {
+                break;
+            }
+            let pt = if c_best.is_finite() {
+                sample_informed(&self.start, &self.goal, c_best, &mut self.rng)
+            } else {
+                self.sampler.sample(&self.goal, &mut self.rng)
+            };
+            if !self.explore_area.is_collision(&pt) || self.is_collision(&pt) {
+                continue;
+            }
+            self.states.push(BITState::new(pt));
+            added += 1;
+        }
+
+        self.kd_tree = KdTree::new();
+        for (id, s) in self.states.iter().enumerate() {
+            if !s.pruned {
+                // duplicated points are simply left out of the neighbour search
+                let _ = self.kd_tree.add(&[s.point.0, s.point.1], id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_keeps_the_solution() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        for seed in 0..5 {
+            let mut bitstar = BITStar::new((1.0, 1.0), (9.0, 9.0), vec![], area, 50, 1, 40.0).with_seed(seed);
+            bitstar.plan().expect("free space has a solution");
+            let c_best = bitstar.best_cost();
+
+            // the vertices of the best path sit exactly on the bound g_hat + h_hat = c_best
+            bitstar.prune();
+            assert_eq!(bitstar.best_cost(), c_best);
+            assert!(bitstar.best_path().is_some());
+        }
+    }
+
+    #[test]
+    fn batches_never_lose_the_solution() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let mut bitstar = BITStar::new((1.0, 1.0), (9.0, 9.0), vec![], area, 50, 5, 40.0).with_seed(0);
+        bitstar.plan().expect("free space has a solution");
+        let costs: Vec<f32> = bitstar
+            .batch_paths
+            .iter()
+            .map(|p| p.as_ref().expect("solution after every batch").path_length())
+            .collect();
+        assert!(costs.windows(2).all(|w| w[1] <= w[0]));
+    }
+}
@@ -1,31 +1,236 @@
 /// 2D boundary objects that have keep out / keep in areas
-use crate::math::{line_seg_intersects, subtract, Point2D};
+use crate::math::{
+    closest_point_on_segment, euclidean_distance, merge_intervals, segment_distance, subtract, Point2D, Pose2D,
+};
 
-use ncollide2d::math::Isometry;
 use ncollide2d::math::Point;
-use ncollide2d::query::contact_ball_convex_polyhedron;
-use ncollide2d::shape::Ball;
 use ncollide2d::shape::ConvexPolygon;
+use serde::{Deserialize, Serialize};
 
 /// for all collisions, we can determine if a point is inside their area
 pub trait Collision {
     fn is_collision(&self, pt: &Point2D) -> bool;
     fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool;
+
+    /// distance to the boundary, negative inside the area
+    ///
+    /// defaults to zero, every point counts as on the boundary. That keeps clearance and radius
+    /// checks conservative (a disc always collides), shapes should override it.
+    fn signed_distance(&self, _pt: &Point2D) -> f32 {
+        0.0
+    }
+
+    /// point of the boundary closest to pt, defaults to pt itself like the signed distance
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        *pt
+    }
+
+    /// axis aligned box around the area, None if the area is unbounded
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        None
+    }
+
+    /// does a disc of the radius around pt overlap the area
+    fn is_collision_radius(&self, pt: &Point2D, radius: f32) -> bool {
+        self.is_collision(pt) || self.signed_distance(pt) < radius
+    }
+
+    /// does a disc of the radius swept along the segment overlap the area
+    ///
+    /// the distance to a convex area is convex along the segment, so its minimum is found with a
+    /// golden section search. Non convex areas should override this.
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        if self.is_collision_segment(start, end) {
+            return true;
+        }
+        let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+        let inv_phi = (5.0f32.sqrt() - 1.0) / 2.0;
+        let (mut a, mut b) = (0.0f32, 1.0f32);
+        for _ in 0..40 {
+            let c = b - inv_phi * (b - a);
+            let d = a + inv_phi * (b - a);
+            if self.signed_distance(&at(c)) < self.signed_distance(&at(d)) {
+                b = d;
+            } else {
+                a = c;
+            }
+        }
+        [0.0, (a + b) / 2.0, 1.0]
+            .iter()
+            .any(|t| self.is_collision_radius(&at(*t), radius))
+    }
+
+    /// parameter intervals of the segment (0 at start, 1 at end) inside the area, sorted and disjoint
+    ///
+    /// the default sphere traces the signed distance, which never steps over a boundary, and
+    /// bisects the changes. Steps are at least 1e-4 of the segment, thinner parts may be missed.
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let len = euclidean_distance(&subtract(end, start));
+        if len == 0.0 {
+            return if self.is_collision(start) { vec![(0.0, 1.0)] } else { vec![] };
+        }
+        let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+        let mut intervals = Vec::new();
+        let mut inside = self.is_collision(start);
+        let (mut t, mut enter) = (0.0f32, 0.0f32);
+        while t < 1.0 {
+            let next = (t + (self.signed_distance(&at(t)).abs() / len).max(1e-4)).min(1.0);
+            if self.is_collision(&at(next)) != inside {
+                let (mut lo, mut hi) = (t, next);
+                for _ in 0..30 {
+                    let mid = (lo + hi) / 2.0;
+                    if self.is_collision(&at(mid)) == inside {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                if inside {
+                    intervals.push((enter, lo));
+                } else {
+                    enter = hi;
+                }
+                inside = !inside;
+            }
+            t = next;
+        }
+        if inside {
+            intervals.push((enter, 1.0));
+        }
+        intervals
+    }
+
+    /// overlap with a convex polygon given counter clockwise
+    ///
+    /// either an edge touches the area, or the polygon contains all of it. In the second case
+    /// the closest boundary point of the area is inside the polygon too.
+    fn is_collision_convex_polygon(&self, corners: &[Point2D]) -> bool {
+        let n = corners.len();
+        if (0..n).any(|i| self.is_collision_segment(&corners[i], &corners[(i + 1) % n])) {
+            return true;
+        }
+        let center = corners.iter().fold((0.0, 0.0), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+        let center = (center.0 / n as f32, center.1 / n as f32);
+        let inner = self.closest_point(&center);
+        (0..n).all(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % n]);
+            (b.0 - a.0) * (inner.1 - a.1) - (b.1 - a.1) * (inner.0 - a.0) >= 0.0
+        })
+    }
 }
 
 /// a simple rectangle described by min / max values
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct RectangleBounds {
     pub min_pt: Point2D,
     pub max_pt: Point2D,
 }
 
+/// rectangle rotated by yaw around its center
+///
+/// open like RectangleBounds, which it matches for a yaw of zero
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OrientedRectangleBounds {
+    pub center_pt: Point2D,
+    /// half of the length (along yaw) and of the width
+    pub half_extents: (f32, f32),
+    pub yaw: f32,
+}
+
+/// a circle swept along a segment, e.g. a thick barrier
+///
+/// closed like CircleBounds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CapsuleBounds {
+    pub start_pt: Point2D,
+    pub end_pt: Point2D,
+    pub radius: f32,
+}
+
+/// a wall without thickness between two points, only the segment itself collides
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SegmentWall {
+    pub start_pt: Point2D,
+    pub end_pt: Point2D,
+}
+
+/// intervals of a segment inside an area bounded by straight edges
+///
+/// the segment is split where it meets an edge, every piece is then either inside or outside
+/// as a whole, and the meeting points themselves may be inside (closed boundaries)
+fn segment_intervals_by_edges(
+    area: &dyn Collision,
+    start: &Point2D,
+    end: &Point2D,
+    edges: impl Iterator<Item = (Point2D, Point2D)>,
+) -> Vec<(f32, f32)> {
+    let dir = subtract(end, start);
+    let len_sq = dir.0 * dir.0 + dir.1 * dir.1;
+    if len_sq == 0.0 {
+        return if area.is_collision(start) { vec![(0.0, 1.0)] } else { vec![] };
+    }
+    let project = |p: &Point2D| ((p.0 - start.0) * dir.0 + (p.1 - start.1) * dir.1) / len_sq;
+    let mut ts = vec![0.0, 1.0];
+    for (a, b) in edges {
+        if segment_distance(start, end, &a, &b) > 0.0 {
+            continue;
+        }
+        let e = subtract(&b, &a);
+        let denom = dir.0 * e.1 - dir.1 * e.0;
+        if denom != 0.0 {
+            let c = subtract(&a, start);
+            ts.push((c.0 * e.1 - c.1 * e.0) / denom);
+        } else {
+            // collinear overlap, split at the edge ends
+            ts.push(project(&a));
+            ts.push(project(&b));
+        }
+    }
+    let mut ts: Vec<f32> = ts.into_iter().map(|t| t.clamp(0.0, 1.0)).collect();
+    ts.sort_by(|a, b| a.total_cmp(b));
+    ts.dedup();
+
+    let at = |t: f32| (start.0 + t * dir.0, start.1 + t * dir.1);
+    let mut intervals: Vec<(f32, f32)> = ts
+        .iter()
+        .filter(|t| area.is_collision(&at(**t)))
+        .map(|t| (*t, *t))
+        .collect();
+    intervals.extend(
+        ts.windows(2)
+            .filter(|w| area.is_collision(&at((w[0] + w[1]) / 2.0)))
+            .map(|w| (w[0], w[1])),
+    );
+    merge_intervals(intervals)
+}
+
+impl RectangleBounds {
+    /// smallest rectangle containing all points, None without points
+    pub fn from_points(points: &[Point2D]) -> Option<Self> {
+        let first = *points.first()?;
+        Some(points.iter().fold(
+            Self {
+                min_pt: first,
+                max_pt: first,
+            },
+            |b, p| Self {
+                min_pt: (b.min_pt.0.min(p.0), b.min_pt.1.min(p.1)),
+                max_pt: (b.max_pt.0.max(p.0), b.max_pt.1.max(p.1)),
+            },
+        ))
+    }
+}
+
 /// a simple circle with a position
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct CircleBounds {
     pub center_pt: Point2D,
     pub radius: f32,
 }
 
 /// convex polygon described from points
+///
+/// non convex point sets are replaced by their convex hull, see PolygonBounds for those
 pub struct ConvexPolygonBounds {
     pub points: Vec<Point2D>,
     convex_poly: ConvexPolygon<f32>,
@@ -57,41 +262,341 @@ impl ConvexPolygonBounds {
             convex_poly: cp,
         })
     }
+
+    /// corners of the convex hull, counter clockwise
+    pub fn hull(&self) -> Vec<Point2D> {
+        self.convex_poly.points().iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    /// 1 for a counter clockwise hull, -1 for a clockwise one
+    fn orientation(&self) -> f32 {
+        let hull = self.convex_poly.points();
+        let area: f32 = (0..hull.len())
+            .map(|i| {
+                let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+        if area >= 0.0 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
 }
 
-impl Collision for ConvexPolygonBounds {
+/// simple polygon, possibly non convex and with holes
+///
+/// the outline and the holes are rings of points in either orientation, without repeating the
+/// first point. Like the convex polygon the area is closed, touching any ring collides.
+#[derive(Debug, Clone)]
+pub struct PolygonBounds {
+    pub exterior: Vec<Point2D>,
+    pub holes: Vec<Vec<Point2D>>,
+}
+
+impl PolygonBounds {
+    /// None if the outline has fewer than three points
+    pub fn new(exterior: &[Point2D]) -> Option<Self> {
+        Self::new_with_holes(exterior, &[])
+    }
+
+    /// None if the outline or a hole has fewer than three points
+    pub fn new_with_holes(exterior: &[Point2D], holes: &[Vec<Point2D>]) -> Option<Self> {
+        if exterior.len() < 3 || holes.iter().any(|h| h.len() < 3) {
+            return None;
+        }
+        Some(Self {
+            exterior: exterior.to_vec(),
+            holes: holes.to_vec(),
+        })
+    }
+
+    /// edges of the outline and of every hole
+    pub fn edges(&self) -> impl Iterator<Item = (Point2D, Point2D)> + '_ {
+        std::iter::once(&self.exterior)
+            .chain(self.holes.iter())
+            .flat_map(|ring| (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()])))
+    }
+
+    fn boundary_distance(&self, pt: &Point2D) -> f32 {
+        euclidean_distance(&subtract(pt, &self.closest_point(pt)))
+    }
+}
+
+impl Collision for PolygonBounds {
+    /// even-odd rule over all rings, so holes are free space
     fn is_collision(&self, pt: &Point2D) -> bool {
-        let prediction = 0.0;
-        let ball_pos = Point::new(pt.0, pt.1);
-        let cp_pos = Isometry::identity();
-        let ball = Ball::<f32>::new(0.0);
-
-        let contact = contact_ball_convex_polyhedron(
-            &ball_pos,
-            &ball,
-            &cp_pos,
-            &self.convex_poly,
-            prediction,
-        );
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.1 > pt.1) != (b.1 > pt.1) {
+                let x = a.0 + (pt.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+                if pt.0 < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside || self.boundary_distance(pt) == 0.0
+    }
+
+    /// a segment starting outside has to touch a ring to get in
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.is_collision(start) || self.edges().any(|(a, b)| segment_distance(start, end, &a, &b) == 0.0)
+    }
+
+    /// exact, the distance to a non convex area isn't convex along the segment
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        self.is_collision_segment(start, end)
+            || self.edges().any(|(a, b)| segment_distance(start, end, &a, &b) < radius)
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        RectangleBounds::from_points(&self.exterior)
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        segment_intervals_by_edges(self, start, end, self.edges())
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        let d = self.boundary_distance(pt);
+        if self.is_collision(pt) {
+            -d
+        } else {
+            d
+        }
+    }
+
+    /// closest point over the edges of all rings
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        self.edges()
+            .map(|(a, b)| closest_point_on_segment(pt, &a, &b))
+            .min_by(|a, b| {
+                let da = euclidean_distance(&subtract(pt, a));
+                let db = euclidean_distance(&subtract(pt, b));
+                da.total_cmp(&db)
+            })
+            .expect("polygon has no points")
+    }
+}
+
+/// racing track as a keep in region between two parallel curves
+/// --
+///
+/// the walls are the centreline offset by the left and right widths of each vertex, with
+/// mitred joints, so constant widths give walls parallel to every centreline segment. Points
+/// outside the corridor (and on its walls) collide. A closed track is a loop whose last point
+/// connects back to the first, an open one is capped at both ends. Widths larger than the
+/// turning radius make the inner wall fold over itself.
+#[derive(Debug, Clone)]
+pub struct TrackCorridor {
+    pub centreline: Vec<Point2D>,
+    pub left_widths: Vec<f32>,
+    pub right_widths: Vec<f32>,
+    pub closed: bool,
+    /// left wall, one point per centreline point
+    pub left: Vec<Point2D>,
+    /// right wall, one point per centreline point
+    pub right: Vec<Point2D>,
+    /// free space between the walls
+    region: PolygonBounds,
+}
+
+/// longest miter relative to the width, limits spikes at hairpins
+const MAX_MITER: f32 = 4.0;
 
-        contact.is_some()
+impl TrackCorridor {
+    /// None if the widths don't match the centreline, or there are too few points
+    /// (two for an open track, three for a closed one)
+    pub fn new(centreline: &[Point2D], left_widths: &[f32], right_widths: &[f32], closed: bool) -> Option<Self> {
+        let n = centreline.len();
+        if n < 2 || (closed && n < 3) || left_widths.len() != n || right_widths.len() != n {
+            return None;
+        }
+
+        let unit = |a: &Point2D, b: &Point2D| {
+            let d = subtract(b, a);
+            let len = euclidean_distance(&d);
+            if len > 0.0 {
+                (d.0 / len, d.1 / len)
+            } else {
+                (0.0, 0.0)
+            }
+        };
+        let (mut left, mut right) = (Vec::with_capacity(n), Vec::with_capacity(n));
+        for i in 0..n {
+            let prev = if i > 0 {
+                Some(unit(&centreline[i - 1], &centreline[i]))
+            } else if closed {
+                Some(unit(&centreline[n - 1], &centreline[0]))
+            } else {
+                None
+            };
+            let next = if i + 1 < n {
+                Some(unit(&centreline[i], &centreline[i + 1]))
+            } else if closed {
+                Some(unit(&centreline[n - 1], &centreline[0]))
+            } else {
+                None
+            };
+            let dir = match (prev, next) {
+                (Some(p), Some(q)) => unit(&(0.0, 0.0), &(p.0 + q.0, p.1 + q.1)),
+                (Some(d), None) | (None, Some(d)) => d,
+                (None, None) => (0.0, 0.0),
+            };
+            // left normal, stretched so the walls stay parallel to the adjacent segments
+            let normal = (-dir.1, dir.0);
+            let seg = next.or(prev).unwrap_or(dir);
+            let cos = normal.0 * -seg.1 + normal.1 * seg.0;
+            let miter = if cos > 1.0 / MAX_MITER { 1.0 / cos } else { MAX_MITER };
+
+            let c = centreline[i];
+            let (l, r) = (left_widths[i] * miter, right_widths[i] * miter);
+            left.push((c.0 + normal.0 * l, c.1 + normal.1 * l));
+            right.push((c.0 - normal.0 * r, c.1 - normal.1 * r));
+        }
+
+        let region = if closed {
+            PolygonBounds::new_with_holes(&left, &[right.clone()])?
+        } else {
+            let ring: Vec<Point2D> = left.iter().chain(right.iter().rev()).copied().collect();
+            PolygonBounds::new(&ring)?
+        };
+        Some(Self {
+            centreline: centreline.to_vec(),
+            left_widths: left_widths.to_vec(),
+            right_widths: right_widths.to_vec(),
+            closed,
+            left,
+            right,
+            region,
+        })
     }
 
+    /// same widths along the whole centreline
+    pub fn new_constant_width(centreline: &[Point2D], left_width: f32, right_width: f32, closed: bool) -> Option<Self> {
+        let n = centreline.len();
+        Self::new(centreline, &vec![left_width; n], &vec![right_width; n], closed)
+    }
+
+    /// wall segments, including the end caps of an open track
+    pub fn edges(&self) -> impl Iterator<Item = (Point2D, Point2D)> + '_ {
+        self.region.edges()
+    }
+}
+
+impl Collision for TrackCorridor {
+    /// outside the walls, or on them
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.region.signed_distance(pt) >= 0.0
+    }
+
+    /// a segment starting inside has to touch a wall to get out
     fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
-        // sampling based
-        // FIXME: bad
-        let npts = 100;
-        let diff = subtract(&end, &start);
-        for idx in 0..npts {
-            let pt = (
-                start.0 + (idx as f32) * diff.0 / (npts as f32),
-                start.1 + (idx as f32) * diff.1 / (npts as f32),
-            );
-            if self.is_collision(&pt) {
-                return true;
+        self.is_collision(start) || self.edges().any(|(a, b)| segment_distance(start, end, &a, &b) == 0.0)
+    }
+
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        self.is_collision_segment(start, end)
+            || self.edges().any(|(a, b)| segment_distance(start, end, &a, &b) < radius)
+    }
+
+    /// everything outside the walls collides
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        None
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        segment_intervals_by_edges(self, start, end, self.edges())
+    }
+
+    /// distance to the closest wall, negative outside the corridor
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        -self.region.signed_distance(pt)
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        self.region.closest_point(pt)
+    }
+}
+
+impl Collision for ConvexPolygonBounds {
+    /// inside or on every edge of the hull, the polygon is closed
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        let hull = self.convex_poly.points();
+        let sign = self.orientation();
+        (0..hull.len()).all(|i| {
+            let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+            sign * ((b.x - a.x) * (pt.1 - a.y) - (b.y - a.y) * (pt.0 - a.x)) >= 0.0
+        })
+    }
+
+    /// clip the segment against the half planes of the hull edges (Cyrus-Beck)
+    ///
+    /// the polygon is closed like in is_collision, touching a vertex or an edge collides
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        let hull = self.convex_poly.points();
+        // orientation of the hull, so the edge normals point outward
+        let sign = self.orientation();
+
+        let dir = subtract(end, start);
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+        for i in 0..hull.len() {
+            let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+            let normal = (sign * (b.y - a.y), -sign * (b.x - a.x));
+            // the segment is inside the edge's half plane where num + t * den <= 0
+            let num = normal.0 * (start.0 - a.x) + normal.1 * (start.1 - a.y);
+            let den = normal.0 * dir.0 + normal.1 * dir.1;
+            if den == 0.0 {
+                if num > 0.0 {
+                    return false;
+                }
+            } else if den > 0.0 {
+                t1 = t1.min(-num / den);
+            } else {
+                t0 = t0.max(-num / den);
             }
+            if t0 > t1 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        let hull: Vec<Point2D> = self.convex_poly.points().iter().map(|p| (p.x, p.y)).collect();
+        RectangleBounds::from_points(&hull)
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let hull: Vec<Point2D> = self.convex_poly.points().iter().map(|p| (p.x, p.y)).collect();
+        let edges = (0..hull.len()).map(|i| (hull[i], hull[(i + 1) % hull.len()]));
+        segment_intervals_by_edges(self, start, end, edges)
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        let d = euclidean_distance(&subtract(pt, &self.closest_point(pt)));
+        if self.is_collision(pt) {
+            -d
+        } else {
+            d
         }
-        return false;
+    }
+
+    /// closest point over the hull edges
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        let hull = self.convex_poly.points();
+        (0..hull.len())
+            .map(|i| {
+                let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+                closest_point_on_segment(pt, &(a.x, a.y), &(b.x, b.y))
+            })
+            .min_by(|a, b| {
+                let da = euclidean_distance(&subtract(pt, a));
+                let db = euclidean_distance(&subtract(pt, b));
+                da.total_cmp(&db)
+            })
+            .expect("convex polygon has no points")
     }
 }
 
@@ -100,13 +605,274 @@ impl Collision for RectangleBounds {
         pt.0 > self.min_pt.0 && pt.0 < self.max_pt.0 && pt.1 > self.min_pt.1 && pt.1 < self.max_pt.1
     }
 
+    /// clip the segment against the x and y slabs
+    ///
+    /// the rectangle is open like in is_collision, a segment along the border doesn't collide
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        let dir = subtract(end, start);
+        let (mut lo, mut hi) = (0.0f32, 1.0f32);
+        for (s, d, min, max) in [
+            (start.0, dir.0, self.min_pt.0, self.max_pt.0),
+            (start.1, dir.1, self.min_pt.1, self.max_pt.1),
+        ] {
+            if d == 0.0 {
+                if s <= min || s >= max {
+                    return false;
+                }
+                continue;
+            }
+            let (ta, tb) = ((min - s) / d, (max - s) / d);
+            lo = lo.max(ta.min(tb));
+            hi = hi.min(ta.max(tb));
+        }
+        // the slabs are open intervals, so an overlap of a single parameter doesn't count
+        lo < hi
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        Some(*self)
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let (min, max) = (self.min_pt, self.max_pt);
+        let corners = [min, (max.0, min.1), max, (min.0, max.1)];
+        let edges = (0..4).map(|i| (corners[i], corners[(i + 1) % 4]));
+        segment_intervals_by_edges(self, start, end, edges)
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        // per axis distance outside the rectangle (negative inside)
+        let dx = (self.min_pt.0 - pt.0).max(pt.0 - self.max_pt.0);
+        let dy = (self.min_pt.1 - pt.1).max(pt.1 - self.max_pt.1);
+        let outside = euclidean_distance(&(dx.max(0.0), dy.max(0.0)));
+        outside + dx.max(dy).min(0.0)
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        let clamped = (
+            pt.0.clamp(self.min_pt.0, self.max_pt.0),
+            pt.1.clamp(self.min_pt.1, self.max_pt.1),
+        );
+        if clamped != *pt {
+            return clamped;
+        }
+        // inside, move to the nearest side
+        let sides = [
+            (pt.0 - self.min_pt.0, (self.min_pt.0, pt.1)),
+            (self.max_pt.0 - pt.0, (self.max_pt.0, pt.1)),
+            (pt.1 - self.min_pt.1, (pt.0, self.min_pt.1)),
+            (self.max_pt.1 - pt.1, (pt.0, self.max_pt.1)),
+        ];
+        sides
+            .iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_d, p)| *p)
+            .unwrap()
+    }
+}
+
+impl OrientedRectangleBounds {
+    pub fn new(center_pt: Point2D, half_extents: (f32, f32), yaw: f32) -> Self {
+        Self {
+            center_pt,
+            half_extents,
+            yaw,
+        }
+    }
+
+    /// rectangle of the given length (along the heading) and width centered on a pose
+    pub fn from_pose(pose: &Pose2D, length: f32, width: f32) -> Self {
+        Self::new((pose.0, pose.1), (length / 2.0, width / 2.0), pose.2)
+    }
+
+    /// corners, counter clockwise starting front left
+    pub fn corners(&self) -> [Point2D; 4] {
+        let (hl, hw) = self.half_extents;
+        [(hl, hw), (-hl, hw), (-hl, -hw), (hl, -hw)].map(|p| self.world_point(&p))
+    }
+
+    /// the same rectangle in its own frame
+    fn local_bounds(&self) -> RectangleBounds {
+        let (hl, hw) = self.half_extents;
+        RectangleBounds {
+            min_pt: (-hl, -hw),
+            max_pt: (hl, hw),
+        }
+    }
+
+    fn local_point(&self, pt: &Point2D) -> Point2D {
+        let (dx, dy) = subtract(pt, &self.center_pt);
+        let (c, s) = (self.yaw.cos(), self.yaw.sin());
+        (c * dx + s * dy, -s * dx + c * dy)
+    }
+
+    fn world_point(&self, pt: &Point2D) -> Point2D {
+        let (c, s) = (self.yaw.cos(), self.yaw.sin());
+        (
+            self.center_pt.0 + c * pt.0 - s * pt.1,
+            self.center_pt.1 + s * pt.0 + c * pt.1,
+        )
+    }
+}
+
+/// all tests run on the axis aligned rectangle in the local frame
+impl Collision for OrientedRectangleBounds {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.local_bounds().is_collision(&self.local_point(pt))
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.local_bounds()
+            .is_collision_segment(&self.local_point(start), &self.local_point(end))
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        RectangleBounds::from_points(&self.corners())
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        self.local_bounds()
+            .segment_intervals(&self.local_point(start), &self.local_point(end))
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        self.local_bounds().signed_distance(&self.local_point(pt))
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        self.world_point(&self.local_bounds().closest_point(&self.local_point(pt)))
+    }
+}
+
+impl CapsuleBounds {
+    pub fn new(start_pt: Point2D, end_pt: Point2D, radius: f32) -> Self {
+        Self {
+            start_pt,
+            end_pt,
+            radius,
+        }
+    }
+
+    /// capsule of the given total length (along the heading) and radius centered on a pose
+    pub fn from_pose(pose: &Pose2D, length: f32, radius: f32) -> Self {
+        let half = (length / 2.0 - radius).max(0.0);
+        let (dx, dy) = (half * pose.2.cos(), half * pose.2.sin());
+        Self::new((pose.0 - dx, pose.1 - dy), (pose.0 + dx, pose.1 + dy), radius)
+    }
+
+    fn axis_distance(&self, pt: &Point2D) -> f32 {
+        euclidean_distance(&subtract(pt, &closest_point_on_segment(pt, &self.start_pt, &self.end_pt)))
+    }
+}
+
+impl Collision for CapsuleBounds {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.axis_distance(pt) <= self.radius
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        segment_distance(start, end, &self.start_pt, &self.end_pt) <= self.radius
+    }
+
+    /// the capsule simply grows by the radius
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        segment_distance(start, end, &self.start_pt, &self.end_pt) <= self.radius + radius
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        let b = RectangleBounds::from_points(&[self.start_pt, self.end_pt])?;
+        Some(RectangleBounds {
+            min_pt: (b.min_pt.0 - self.radius, b.min_pt.1 - self.radius),
+            max_pt: (b.max_pt.0 + self.radius, b.max_pt.1 + self.radius),
+        })
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        self.axis_distance(pt) - self.radius
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        let axis_pt = closest_point_on_segment(pt, &self.start_pt, &self.end_pt);
+        let diff = subtract(pt, &axis_pt);
+        let d = euclidean_distance(&diff);
+        let axis = subtract(&self.end_pt, &self.start_pt);
+        let len = euclidean_distance(&axis);
+        let on_cap = axis_pt == self.start_pt || axis_pt == self.end_pt;
+        let (ux, uy) = if d > 0.0 && (on_cap || len == 0.0) {
+            (diff.0 / d, diff.1 / d)
+        } else if len > 0.0 {
+            // beside the axis go sideways, rounding makes diff useless for points on the axis
+            let side = if axis.0 * diff.1 - axis.1 * diff.0 >= 0.0 { 1.0 } else { -1.0 };
+            (-side * axis.1 / len, side * axis.0 / len)
+        } else {
+            (1.0, 0.0)
+        };
+        (axis_pt.0 + self.radius * ux, axis_pt.1 + self.radius * uy)
+    }
+}
+
+impl SegmentWall {
+    pub fn new(start_pt: Point2D, end_pt: Point2D) -> Self {
+        Self { start_pt, end_pt }
+    }
+
+    /// wall of the given length along the heading, centered on a pose
+    pub fn from_pose(pose: &Pose2D, length: f32) -> Self {
+        let (dx, dy) = (length / 2.0 * pose.2.cos(), length / 2.0 * pose.2.sin());
+        Self::new((pose.0 - dx, pose.1 - dy), (pose.0 + dx, pose.1 + dy))
+    }
+}
+
+impl Collision for SegmentWall {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.signed_distance(pt) == 0.0
+    }
+
     fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
-        let p0 = (self.min_pt.0, self.max_pt.1);
-        let p1 = (self.max_pt.0, self.min_pt.1);
-        line_seg_intersects(&start, &end, &self.min_pt, &p0)
-            || line_seg_intersects(&start, &end, &self.min_pt, &p1)
-            || line_seg_intersects(&start, &end, &self.max_pt, &p0)
-            || line_seg_intersects(&start, &end, &self.max_pt, &p1)
+        segment_distance(start, end, &self.start_pt, &self.end_pt) == 0.0
+    }
+
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        let d = segment_distance(start, end, &self.start_pt, &self.end_pt);
+        d == 0.0 || d < radius
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        RectangleBounds::from_points(&[self.start_pt, self.end_pt])
+    }
+
+    /// where the segment meets the wall, a single point unless the two are collinear
+    ///
+    /// the rounded meeting point is rarely exactly on the wall, so it isn't checked again
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        if !self.is_collision_segment(start, end) {
+            return vec![];
+        }
+        let dir = subtract(end, start);
+        let len_sq = dir.0 * dir.0 + dir.1 * dir.1;
+        if len_sq == 0.0 {
+            return vec![(0.0, 1.0)];
+        }
+        let e = subtract(&self.end_pt, &self.start_pt);
+        let denom = dir.0 * e.1 - dir.1 * e.0;
+        if denom != 0.0 {
+            let c = subtract(&self.start_pt, start);
+            let t = ((c.0 * e.1 - c.1 * e.0) / denom).clamp(0.0, 1.0);
+            vec![(t, t)]
+        } else {
+            let project = |p: &Point2D| ((p.0 - start.0) * dir.0 + (p.1 - start.1) * dir.1) / len_sq;
+            let (a, b) = (project(&self.start_pt), project(&self.end_pt));
+            vec![(a.min(b).max(0.0), a.max(b).min(1.0))]
+        }
+    }
+
+    /// the wall has no inside, the distance is never negative
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        euclidean_distance(&subtract(pt, &self.closest_point(pt)))
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        closest_point_on_segment(pt, &self.start_pt, &self.end_pt)
     }
 }
 
@@ -117,16 +883,255 @@ impl Collision for CircleBounds {
         (dx * dx + dy * dy) <= (self.radius * self.radius)
     }
 
-    /// line equation collision with obstacles
+    /// distance from the center to the closest point of the segment
     fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
-        let (x1, y1) = start;
-        let (x2, y2) = end;
+        self.is_collision(&closest_point_on_segment(&self.center_pt, start, end))
+    }
+
+    /// the circle simply grows by the radius
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        let closest = closest_point_on_segment(&self.center_pt, start, end);
+        euclidean_distance(&subtract(&closest, &self.center_pt)) <= self.radius + radius
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        Some(RectangleBounds {
+            min_pt: (self.center_pt.0 - self.radius, self.center_pt.1 - self.radius),
+            max_pt: (self.center_pt.0 + self.radius, self.center_pt.1 + self.radius),
+        })
+    }
+
+    /// roots of the distance to the center along the segment
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let dir = subtract(end, start);
+        let rel = subtract(start, &self.center_pt);
+        let a = dir.0 * dir.0 + dir.1 * dir.1;
+        if a == 0.0 {
+            return if self.is_collision(start) { vec![(0.0, 1.0)] } else { vec![] };
+        }
+        let b = 2.0 * (dir.0 * rel.0 + dir.1 * rel.1);
+        let c = rel.0 * rel.0 + rel.1 * rel.1 - self.radius * self.radius;
+        let disc = b * b - 4.0 * a * c;
+        if disc < 0.0 {
+            return vec![];
+        }
+        let lo = ((-b - disc.sqrt()) / (2.0 * a)).max(0.0);
+        let hi = ((-b + disc.sqrt()) / (2.0 * a)).min(1.0);
+        if lo > hi {
+            vec![]
+        } else {
+            vec![(lo, hi)]
+        }
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        euclidean_distance(&subtract(pt, &self.center_pt)) - self.radius
+    }
 
-        let a = y2 - y1;
-        let b = -(x2 - x1);
-        let c = y2 * (x2 - x1) - x2 * (y2 - y1);
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        let diff = subtract(pt, &self.center_pt);
+        let d = euclidean_distance(&diff);
+        // any boundary point is closest to the center
+        let (ux, uy) = if d > 0.0 { (diff.0 / d, diff.1 / d) } else { (1.0, 0.0) };
+        (self.center_pt.0 + self.radius * ux, self.center_pt.1 + self.radius * uy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csg::{Complement, Difference, Intersection, Union};
+    use crate::footprint::InflatedBounds;
+    use crate::grid::OccupancyGrid;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// point along the segment, t from 0 at start to 1 at end
+    fn at(start: &Point2D, end: &Point2D, t: f32) -> Point2D {
+        (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1))
+    }
+
+    /// compare the segment tests against dense samples along the segment
+    ///
+    /// samples within 1e-4 of the boundary are left out, and a colliding segment must pass
+    /// within the sample spacing of the area (thin areas fall between the samples)
+    fn check_segment(name: &str, shape: &dyn Collision, start: &Point2D, end: &Point2D) {
+        let n = 1000;
+        let spacing = euclidean_distance(&subtract(end, start)) / n as f32;
+        let intervals = shape.segment_intervals(start, end);
+        let collides = shape.is_collision_segment(start, end);
+        let msg = format!("{name} {start:?} {end:?}: {intervals:?}");
+        let mut closest = f32::INFINITY;
+        for i in 0..=n {
+            let t = i as f32 / n as f32;
+            let pt = at(start, end, t);
+            let d = shape.signed_distance(&pt);
+            closest = closest.min(d);
+            if d.abs() > 1e-4 {
+                let in_interval = intervals.iter().any(|(a, b)| *a <= t && t <= *b);
+                assert_eq!(in_interval, shape.is_collision(&pt), "{msg} at {t}");
+            }
+        }
+        assert_eq!(!intervals.is_empty(), collides, "{msg}");
+        if closest < -1e-4 {
+            assert!(collides, "{msg}");
+        }
+        if collides {
+            assert!(closest <= spacing + 1e-4, "{msg}");
+        }
+    }
+
+    /// cross check the queries of a shape on a grid of points and on random segments
+    ///
+    /// the closest point and the distance are only checked where they are exact, the CSG
+    /// combinators give bounds
+    fn check_shape(name: &str, shape: &dyn Collision, exact: bool) {
+        let tol = 1e-3;
+        let bbox = shape.bounding_box();
+        for i in 0..=40 {
+            for j in 0..=40 {
+                let pt = (-1.0 + 0.3 * i as f32, -1.0 + 0.3 * j as f32);
+                let d = shape.signed_distance(&pt);
+                let inside = shape.is_collision(&pt);
+                if inside {
+                    assert!(d <= tol, "{name} {pt:?}: inside at {d}");
+                } else {
+                    assert!(d >= -tol, "{name} {pt:?}: outside at {d}");
+                }
+                if let (Some(b), true) = (bbox, inside) {
+                    let contained = b.min_pt.0 - tol <= pt.0
+                        && pt.0 <= b.max_pt.0 + tol
+                        && b.min_pt.1 - tol <= pt.1
+                        && pt.1 <= b.max_pt.1 + tol;
+                    assert!(contained, "{name} {pt:?} outside {b:?}");
+                }
+                if exact && d.is_finite() {
+                    let cp = shape.closest_point(&pt);
+                    let on_boundary = shape.signed_distance(&cp).abs();
+                    assert!(on_boundary <= tol, "{name} {pt:?}: {cp:?} at {on_boundary}");
+                    let gap = euclidean_distance(&subtract(&pt, &cp)) - d.abs();
+                    assert!(gap.abs() <= tol, "{name} {pt:?}: {cp:?} off by {gap}");
+                }
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut endpoint = || (rng.gen_range(-1.0..11.0), rng.gen_range(-1.0..11.0));
+            let (start, end) = (endpoint(), endpoint());
+            check_segment(name, shape, &start, &end);
+        }
+    }
+
+    #[test]
+    fn convex_polygon_segments_match_samples() {
+        let corners = [(2.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 2.0)];
+        let diamond = ConvexPolygonBounds::new_from_points(&corners).unwrap();
+        let cases: [(Point2D, Point2D, Vec<(f32, f32)>); 8] = [
+            // through two opposite vertices
+            ((-1.0, 2.0), (5.0, 2.0), vec![(1.0 / 6.0, 5.0 / 6.0)]),
+            // grazing the top vertex
+            ((0.0, 4.0), (4.0, 4.0), vec![(0.5, 0.5)]),
+            // collinear with an edge, overlapping it in the middle
+            ((1.0, -1.0), (5.0, 3.0), vec![(0.25, 0.75)]),
+            // collinear with an edge, but beyond its end
+            ((5.0, 3.0), (6.0, 4.0), vec![]),
+            // just above the top vertex
+            ((0.0, 4.1), (4.0, 4.1), vec![]),
+            // from the inside out
+            ((2.0, 2.0), (6.0, 2.0), vec![(0.0, 0.5)]),
+            // fully inside
+            ((1.5, 2.0), (2.5, 2.0), vec![(0.0, 1.0)]),
+            // starting on a vertex, leaving
+            ((4.0, 2.0), (6.0, 2.0), vec![(0.0, 0.0)]),
+        ];
+        for (start, end, expected) in cases.iter() {
+            check_segment("convex polygon", &diamond, start, end);
+            let intervals = diamond.segment_intervals(start, end);
+            assert_eq!(intervals.len(), expected.len(), "{start:?} {end:?}: {intervals:?}");
+            for (got, want) in intervals.iter().zip(expected) {
+                let close = (got.0 - want.0).abs() < 1e-5 && (got.1 - want.1).abs() < 1e-5;
+                assert!(close, "{got:?} {want:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn every_shape_is_consistent() {
+        let rect = RectangleBounds {
+            min_pt: (2.0, 3.0),
+            max_pt: (6.0, 5.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        let corners = [(2.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 2.0)];
+        let diamond = ConvexPolygonBounds::new_from_points(&corners).unwrap();
+        let polygon = PolygonBounds::new_with_holes(
+            &[(1.0, 1.0), (9.0, 1.0), (9.0, 4.0), (4.0, 4.0), (4.0, 9.0), (1.0, 9.0)],
+            &[vec![(2.0, 2.0), (3.0, 2.0), (3.0, 3.0), (2.0, 3.0)]],
+        )
+        .unwrap();
+        let track = TrackCorridor::new_constant_width(
+            &[(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)],
+            1.0,
+            1.0,
+            true,
+        )
+        .unwrap();
+        let mut cells = vec![0; 100];
+        for idx in [11, 12, 22, 45, 46, 55, 56, 78] {
+            cells[idx] = 100;
+        }
+        let mut grid = OccupancyGrid::new((0.0, 0.0), 1.0, 10, 10, cells).unwrap();
+        grid.unknown_is_occupied = false;
+
+        let oriented = OrientedRectangleBounds::new((5.0, 5.0), (2.0, 1.0), 0.6);
+        let pair = || -> Vec<Box<dyn Collision>> { vec![Box::new(rect), Box::new(circle)] };
+
+        let shapes: Vec<(&str, Box<dyn Collision>, bool)> = vec![
+            ("rectangle", Box::new(rect), true),
+            ("oriented rectangle", Box::new(oriented), true),
+            ("capsule", Box::new(CapsuleBounds::new((2.0, 2.0), (7.0, 6.0), 1.0)), true),
+            ("segment wall", Box::new(SegmentWall::new((1.0, 8.0), (8.0, 2.0))), true),
+            ("circle", Box::new(circle), true),
+            ("convex polygon", Box::new(diamond), true),
+            ("polygon", Box::new(polygon), true),
+            ("track", Box::new(track), true),
+            ("occupancy grid", Box::new(grid), true),
+            ("inflated", Box::new(InflatedBounds::new(&rect, 0.5)), true),
+            ("union", Box::new(Union::new(pair())), false),
+            ("intersection", Box::new(Intersection::new(pair())), false),
+            ("difference", Box::new(Difference::new(Box::new(circle), Box::new(rect))), false),
+            ("complement", Box::new(Complement::new(Box::new(circle))), false),
+        ];
+        for (name, shape, exact) in shapes.iter() {
+            check_shape(name, shape.as_ref(), *exact);
+        }
+    }
+
+    /// only the methods the trait started out with
+    struct PointsOnly;
+
+    impl Collision for PointsOnly {
+        fn is_collision(&self, pt: &Point2D) -> bool {
+            pt.0 < 1.0
+        }
+
+        fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+            start.0 < 1.0 || end.0 < 1.0
+        }
+    }
 
-        let d = (a * self.center_pt.0 + b * self.center_pt.1 + c).abs() / (a * a + b * b);
-        d <= (self.radius * self.radius)
+    #[test]
+    fn defaults_are_conservative() {
+        let far = (5.0, 0.0);
+        assert_eq!(PointsOnly.signed_distance(&far), 0.0);
+        assert_eq!(PointsOnly.closest_point(&far), far);
+        assert!(PointsOnly.bounding_box().is_none());
+        assert!(PointsOnly.is_collision_radius(&far, 0.1));
+        assert!(PointsOnly.is_collision_segment_radius(&far, &(6.0, 0.0), 0.1));
+        assert!(PointsOnly.is_collision_convex_polygon(&[(4.0, -1.0), (6.0, -1.0), (6.0, 1.0), (4.0, 1.0)]));
+        assert_eq!(PointsOnly.segment_intervals(&(0.0, 0.0), &(2.0, 0.0)).len(), 1);
     }
 }
@@ -0,0 +1,217 @@
+/// Constructive solid geometry over obstacles
+///
+/// segment tests combine the intervals of the segment inside every operand, so they are as
+/// exact as the operands' segment_intervals. Signed distances use the usual min / max rules,
+/// exact outside a union and a bound of the distance otherwise.
+use crate::bound::{Collision, RectangleBounds};
+use crate::math::{complement_intervals, intersect_intervals, merge_intervals, Point2D};
+
+/// inside any of the children
+pub struct Union<'a> {
+    pub children: Vec<Box<dyn Collision + 'a>>,
+}
+
+/// inside all of the children
+pub struct Intersection<'a> {
+    pub children: Vec<Box<dyn Collision + 'a>>,
+}
+
+/// inside base but not inside cut
+pub struct Difference<'a> {
+    pub base: Box<dyn Collision + 'a>,
+    pub cut: Box<dyn Collision + 'a>,
+}
+
+/// everything outside inner, turns a keep out area into a keep in one and back
+pub struct Complement<'a> {
+    pub inner: Box<dyn Collision + 'a>,
+}
+
+impl<'a> Union<'a> {
+    pub fn new(children: Vec<Box<dyn Collision + 'a>>) -> Self {
+        Self { children }
+    }
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(children: Vec<Box<dyn Collision + 'a>>) -> Self {
+        Self { children }
+    }
+}
+
+impl<'a> Difference<'a> {
+    pub fn new(base: Box<dyn Collision + 'a>, cut: Box<dyn Collision + 'a>) -> Self {
+        Self { base, cut }
+    }
+}
+
+impl<'a> Complement<'a> {
+    pub fn new(inner: Box<dyn Collision + 'a>) -> Self {
+        Self { inner }
+    }
+}
+
+/// child with the smallest (or largest) signed distance
+fn extreme_child<'b>(
+    children: &'b [Box<dyn Collision + '_>],
+    pt: &Point2D,
+    largest: bool,
+) -> Option<(&'b dyn Collision, f32)> {
+    children
+        .iter()
+        .map(|c| (c.as_ref() as &dyn Collision, c.signed_distance(pt)))
+        .reduce(|a, b| if (b.1 > a.1) == largest { b } else { a })
+}
+
+impl Collision for Union<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.children.iter().any(|c| c.is_collision(pt))
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.children.iter().any(|c| c.is_collision_segment(start, end))
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        merge_intervals(
+            self.children
+                .iter()
+                .flat_map(|c| c.segment_intervals(start, end))
+                .collect(),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        self.children
+            .iter()
+            .map(|c| c.bounding_box())
+            .reduce(|a, b| {
+                let (a, b) = (a?, b?);
+                RectangleBounds::from_points(&[a.min_pt, a.max_pt, b.min_pt, b.max_pt])
+            })
+            .flatten()
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        extreme_child(&self.children, pt, false).map_or(f32::INFINITY, |(_, d)| d)
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        extreme_child(&self.children, pt, false).map_or(*pt, |(c, _)| c.closest_point(pt))
+    }
+}
+
+impl Collision for Intersection<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        !self.children.is_empty() && self.children.iter().all(|c| c.is_collision(pt))
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        !self.segment_intervals(start, end).is_empty()
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let mut children = self.children.iter();
+        let first = match children.next() {
+            Some(c) => c.segment_intervals(start, end),
+            None => return Vec::new(),
+        };
+        children.fold(first, |acc, c| {
+            if acc.is_empty() {
+                return acc;
+            }
+            intersect_intervals(&acc, &c.segment_intervals(start, end))
+        })
+    }
+
+    /// overlap of the bounded children's boxes
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        self.children
+            .iter()
+            .filter_map(|c| c.bounding_box())
+            .reduce(|a, b| RectangleBounds {
+                min_pt: (a.min_pt.0.max(b.min_pt.0), a.min_pt.1.max(b.min_pt.1)),
+                max_pt: (a.max_pt.0.min(b.max_pt.0), a.max_pt.1.min(b.max_pt.1)),
+            })
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        extreme_child(&self.children, pt, true).map_or(f32::INFINITY, |(_, d)| d)
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        extreme_child(&self.children, pt, true).map_or(*pt, |(c, _)| c.closest_point(pt))
+    }
+}
+
+impl Collision for Difference<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.base.is_collision(pt) && !self.cut.is_collision(pt)
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        !self.segment_intervals(start, end).is_empty()
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let base = self.base.segment_intervals(start, end);
+        if base.is_empty() {
+            return base;
+        }
+        let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+        let kept = complement_intervals(&self.cut.segment_intervals(start, end));
+        // the cut's boundary stays with the cut, drop the single points left on it
+        intersect_intervals(&base, &kept)
+            .into_iter()
+            .filter(|(lo, hi)| self.is_collision(&at((lo + hi) / 2.0)))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        self.base.bounding_box()
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        self.base.signed_distance(pt).max(-self.cut.signed_distance(pt))
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        if self.base.signed_distance(pt) >= -self.cut.signed_distance(pt) {
+            self.base.closest_point(pt)
+        } else {
+            self.cut.closest_point(pt)
+        }
+    }
+}
+
+impl Collision for Complement<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        !self.inner.is_collision(pt)
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        !self.segment_intervals(start, end).is_empty()
+    }
+
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+        // the inner boundary belongs to the inner area, drop the single points left on it
+        complement_intervals(&self.inner.segment_intervals(start, end))
+            .into_iter()
+            .filter(|(lo, hi)| self.is_collision(&at((lo + hi) / 2.0)))
+            .collect()
+    }
+
+    /// everything outside a bounded area is unbounded
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        None
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        -self.inner.signed_distance(pt)
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        self.inner.closest_point(pt)
+    }
+}
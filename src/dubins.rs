@@ -0,0 +1,188 @@
+/// Dubins paths: shortest forward-only paths for a car with a minimum turning radius
+use crate::math::{mod2pi, wrap_angle, Pose2D};
+use serde::{Deserialize, Serialize};
+
+/// steering wheel position held over a path segment
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Steering {
+    Left,
+    Straight,
+    Right,
+}
+
+/// direction of travel over a path segment
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gear {
+    Forward,
+    Reverse,
+}
+
+/// drive from a pose along a constant steering segment
+///
+/// negative lengths drive the segment in reverse
+pub fn drive(pose: &Pose2D, steering: Steering, length: f32, radius: f32) -> Pose2D {
+    let (x, y, yaw) = *pose;
+    match steering {
+        Steering::Straight => (x + length * yaw.cos(), y + length * yaw.sin(), yaw),
+        Steering::Left => {
+            let nyaw = yaw + length / radius;
+            (
+                x + radius * (nyaw.sin() - yaw.sin()),
+                y - radius * (nyaw.cos() - yaw.cos()),
+                wrap_angle(nyaw),
+            )
+        }
+        Steering::Right => {
+            let nyaw = yaw - length / radius;
+            (
+                x - radius * (nyaw.sin() - yaw.sin()),
+                y + radius * (nyaw.cos() - yaw.cos()),
+                wrap_angle(nyaw),
+            )
+        }
+    }
+}
+
+/// three segment Dubins path
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DubinsPath {
+    pub start: Pose2D,
+    pub turning_radius: f32,
+    pub steering: [Steering; 3],
+    /// segment arc lengths
+    pub lengths: [f32; 3],
+}
+
+/// normalised segment lengths (t, p, q) of a word, or None if it can't connect the poses
+type Word = fn(f32, f32, f32) -> Option<(f32, f32, f32)>;
+
+fn lsl(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let p_sq = 2.0 + d * d - 2.0 * (alpha - beta).cos() + 2.0 * d * (sa - sb);
+    if p_sq < 0.0 {
+        return None;
+    }
+    let tmp = (cb - ca).atan2(d + sa - sb);
+    Some((mod2pi(tmp - alpha), p_sq.sqrt(), mod2pi(beta - tmp)))
+}
+
+fn rsr(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let p_sq = 2.0 + d * d - 2.0 * (alpha - beta).cos() + 2.0 * d * (sb - sa);
+    if p_sq < 0.0 {
+        return None;
+    }
+    let tmp = (ca - cb).atan2(d - sa + sb);
+    Some((mod2pi(alpha - tmp), p_sq.sqrt(), mod2pi(tmp - beta)))
+}
+
+fn lsr(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let p_sq = -2.0 + d * d + 2.0 * (alpha - beta).cos() + 2.0 * d * (sa + sb);
+    if p_sq < 0.0 {
+        return None;
+    }
+    let p = p_sq.sqrt();
+    let tmp = (-ca - cb).atan2(d + sa + sb) - (-2.0f32).atan2(p);
+    Some((mod2pi(tmp - alpha), p, mod2pi(tmp - beta)))
+}
+
+fn rsl(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let p_sq = -2.0 + d * d + 2.0 * (alpha - beta).cos() - 2.0 * d * (sa + sb);
+    if p_sq < 0.0 {
+        return None;
+    }
+    let p = p_sq.sqrt();
+    let tmp = (ca + cb).atan2(d - sa - sb) - 2.0f32.atan2(p);
+    Some((mod2pi(alpha - tmp), p, mod2pi(beta - tmp)))
+}
+
+fn rlr(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let tmp = (6.0 - d * d + 2.0 * (alpha - beta).cos() + 2.0 * d * (sa - sb)) / 8.0;
+    if tmp.abs() > 1.0 {
+        return None;
+    }
+    let p = mod2pi(2.0 * std::f32::consts::PI - tmp.acos());
+    let t = mod2pi(alpha - (ca - cb).atan2(d - sa + sb) + p / 2.0);
+    Some((t, p, mod2pi(alpha - beta - t + p)))
+}
+
+fn lrl(alpha: f32, beta: f32, d: f32) -> Option<(f32, f32, f32)> {
+    let (sa, sb, ca, cb) = (alpha.sin(), beta.sin(), alpha.cos(), beta.cos());
+    let tmp = (6.0 - d * d + 2.0 * (alpha - beta).cos() + 2.0 * d * (sb - sa)) / 8.0;
+    if tmp.abs() > 1.0 {
+        return None;
+    }
+    let p = mod2pi(2.0 * std::f32::consts::PI - tmp.acos());
+    let t = mod2pi(-alpha - (ca - cb).atan2(d + sa - sb) + p / 2.0);
+    Some((t, p, mod2pi(beta - alpha - t + p)))
+}
+
+const WORDS: [(Word, [Steering; 3]); 6] = [
+    (lsl, [Steering::Left, Steering::Straight, Steering::Left]),
+    (rsr, [Steering::Right, Steering::Straight, Steering::Right]),
+    (lsr, [Steering::Left, Steering::Straight, Steering::Right]),
+    (rsl, [Steering::Right, Steering::Straight, Steering::Left]),
+    (rlr, [Steering::Right, Steering::Left, Steering::Right]),
+    (lrl, [Steering::Left, Steering::Right, Steering::Left]),
+];
+
+impl DubinsPath {
+    /// shortest of the six Dubins words from start to goal
+    pub fn shortest(start: &Pose2D, goal: &Pose2D, turning_radius: f32) -> Option<Self> {
+        // work in the frame of the start, scaled to a unit turning radius
+        let dx = goal.0 - start.0;
+        let dy = goal.1 - start.1;
+        let d = (dx * dx + dy * dy).sqrt() / turning_radius;
+        let theta = mod2pi(dy.atan2(dx));
+        let alpha = mod2pi(start.2 - theta);
+        let beta = mod2pi(goal.2 - theta);
+
+        let mut best: Option<Self> = None;
+        for (word, steering) in WORDS.iter() {
+            if let Some((t, p, q)) = word(alpha, beta, d) {
+                let candidate = Self {
+                    start: *start,
+                    turning_radius,
+                    steering: *steering,
+                    lengths: [t * turning_radius, p * turning_radius, q * turning_radius],
+                };
+                if best.as_ref().is_none_or(|b| candidate.length() < b.length()) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        best
+    }
+
+    /// total arc length of the path
+    pub fn length(&self) -> f32 {
+        self.lengths.iter().sum()
+    }
+
+    /// pose after travelling the arc length s along the path
+    pub fn interpolate(&self, s: f32) -> Pose2D {
+        let mut pose = self.start;
+        let mut remaining = s.max(0.0);
+        for (steering, length) in self.steering.iter().zip(self.lengths.iter()) {
+            let l = remaining.min(*length);
+            pose = drive(&pose, *steering, l, self.turning_radius);
+            remaining -= l;
+            if remaining <= 0.0 {
+                break;
+            }
+        }
+        pose
+    }
+
+    /// poses every step along the path up to the arc length max_length, both ends included
+    pub fn sample(&self, step: f32, max_length: f32) -> Vec<Pose2D> {
+        let end = max_length.min(self.length());
+        let n = (end / step).ceil().max(1.0) as usize;
+        (0..=n)
+            .map(|idx| self.interpolate(end * (idx as f32) / (n as f32)))
+            .collect()
+    }
+}
@@ -0,0 +1,414 @@
+use crate::PathTree;
+/// Kinematic Rapidly Exploring Random Trees with Dubins steering
+use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::dubins::{DubinsPath, Gear};
+use crate::kinematic::{PoseRRTStar, PoseTree};
+use crate::math::*;
+use crate::path::{Path2D, PosePath2D};
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::{PoseNode, RRTNode};
+
+/// Dubins RRT Configuration Object
+/// --
+///
+/// nodes carry a heading and are connected by the shortest Dubins path for the
+/// turning radius, so every edge can be followed by a forward-driving car. `rrt`
+/// holds the shared configuration (its start and goal are the positions of the poses).
+pub struct DubinsRRT<'a> {
+    pub rrt: RRT<'a>,
+    pub start_yaw: f32,
+    pub goal_yaw: f32,
+    pub turning_radius: f32,
+    pub node_tree: PathTree<PoseNode>,
+}
+
+/// Dubins RRT* (two more hyperparameters)
+pub struct DubinsRRTStar<'a> {
+    pub dubins_rrt: DubinsRRT<'a>,
+    pub connect_circle_dist: f32,
+    pub search_until_max: bool,
+}
+
+impl Planner<'_> for DubinsRRT<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
+    fn plan(&mut self) -> Option<Path2D> {
+        self.plan_poses().map(|p| p.to_path2d())
+    }
+}
+
+impl Planner<'_> for DubinsRRTStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.dubins_rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.dubins_rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.dubins_rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.dubins_rrt.nominal_speed()
+    }
+
+    fn plan(&mut self) -> Option<Path2D> {
+        self.plan_poses().map(|p| p.to_path2d())
+    }
+}
+
+impl<'a> DubinsRRT<'a> {
+    /// create new tree, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Pose2D,
+        goal: Pose2D,
+        obstacles: Vec<&'a dyn Collision>,
+        turning_radius: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+    ) -> Self {
+        Self {
+            rrt: RRT::new(
+                (start.0, start.1),
+                (goal.0, goal.1),
+                obstacles,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            start_yaw: start.2,
+            goal_yaw: goal.2,
+            turning_radius,
+            node_tree: PathTree::new(),
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    /// Dubins RRT Path Planning
+    ///
+    /// like the other planners the path runs from the goal to the start, the headings
+    /// are those of the vehicle driving from the start
+    pub fn plan_poses(&mut self) -> Option<PosePath2D> {
+        self.node_tree.add_node(self.start_node());
+        let mut push_idx = 1;
+
+        for _idx in 1..=self.rrt.max_iter {
+            let rnd_node = self.get_random_node();
+            let nearest_ind = self
+                .node_tree
+                .get_nearest_node_index(&rnd_node)
+                .expect("node list should have a size > 0");
+            let nearest_node = self
+                .node_tree
+                .get(nearest_ind)
+                .expect("RRT Nearest Node failed to get from node list");
+            let new_node = match self.steer(nearest_node, &rnd_node, self.rrt.expand_dis, push_idx) {
+                Some(n) if !self.is_collision_node(&n) => n,
+                _ => continue,
+            };
+            let near_goal = new_node.distance_between_pos(self.rrt.goal) <= self.rrt.expand_dis;
+            self.node_tree.add_node(new_node);
+            push_idx += 1;
+
+            // close enough, try driving the full Dubins path into the goal pose
+            if near_goal {
+                if let Some(goal_node) = self.connect_goal(push_idx - 1, push_idx) {
+                    let path = self.get_pose_path(&goal_node);
+                    self.node_tree.add_node(goal_node);
+                    return Some(path);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// random pose (exploration), the goal sampling rate picks the goal pose instead
+    pub fn get_random_node(&mut self) -> PoseNode {
+        self.random_pose_node()
+    }
+
+    /// follow the Dubins path from node to node for at most max_length
+    ///
+    /// the new node takes the pose where the path was cut off. None if no path exists.
+    pub fn steer(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode> {
+        let dpath = DubinsPath::shortest(&from_node.pose(), &to_node.pose(), self.turning_radius)?;
+        let poses = dpath.sample(self.rrt.path_resolution, max_length);
+        let (x, y, yaw) = *poses.last().unwrap();
+
+        // snap onto the target when the whole path was driven
+        let reached = dpath.length() <= max_length;
+        let (point, yaw) = if reached {
+            (to_node.point(), to_node.yaw)
+        } else {
+            ((x, y), yaw)
+        };
+
+        Some(PoseNode {
+            node: RRTNode {
+                id: index,
+                parent_id: Some(from_node.id()),
+                point,
+                path: poses.iter().map(|(x, y, _)| (*x, *y)).collect(),
+            },
+            yaw,
+            cost: from_node.cost + dpath.length().min(max_length),
+            gears: vec![Gear::Forward; poses.len() - 1],
+            poses,
+        })
+    }
+
+    /// check the sampled edge curve: every pose must be inside the explore area, and
+    /// every chord between consecutive poses free of obstacles
+    pub fn is_collision_edge(&self, poses: &[Pose2D]) -> bool {
+        PoseTree::is_collision_edge(self, poses)
+    }
+
+    /// poses from the node back to the root, following each edge curve
+    ///
+    /// the node may be outside the tree as long as its parent is in it
+    pub fn get_pose_path(&self, node: &PoseNode) -> PosePath2D {
+        let mut path = vec![node.pose()];
+        let mut current = node;
+        while let Some(parent_id) = current.parent_id() {
+            // the first pose of an edge is its parent, the last one the node itself
+            path.extend(current.poses.iter().rev().skip(1));
+            current = self.node_tree.get(parent_id).unwrap();
+        }
+        PosePath2D(path)
+    }
+}
+
+impl<'a> DubinsRRTStar<'a> {
+    /// create new tree, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Pose2D,
+        goal: Pose2D,
+        obstacles: Vec<&'a dyn Collision>,
+        turning_radius: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    ) -> Self {
+        Self {
+            dubins_rrt: DubinsRRT::new(
+                start,
+                goal,
+                obstacles,
+                turning_radius,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            connect_circle_dist,
+            search_until_max,
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.dubins_rrt = self.dubins_rrt.with_seed(seed);
+        self
+    }
+
+    /// Dubins RRT* Path Planning, the path runs from the goal to the start
+    pub fn plan_poses(&mut self) -> Option<PosePath2D> {
+        let goal_node = self.plan_goal_node()?;
+        Some(self.dubins_rrt.get_pose_path(&goal_node))
+    }
+}
+
+impl<'a> PoseTree<'a> for DubinsRRT<'a> {
+    fn rrt(&self) -> &RRT<'a> {
+        &self.rrt
+    }
+
+    fn rrt_mut(&mut self) -> &mut RRT<'a> {
+        &mut self.rrt
+    }
+
+    fn start_yaw(&self) -> f32 {
+        self.start_yaw
+    }
+
+    fn goal_yaw(&self) -> f32 {
+        self.goal_yaw
+    }
+
+    fn tree(&self) -> &PathTree<PoseNode> {
+        &self.node_tree
+    }
+
+    fn tree_mut(&mut self) -> &mut PathTree<PoseNode> {
+        &mut self.node_tree
+    }
+
+    fn steer_pose(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode> {
+        self.steer(from_node, to_node, max_length, index)
+    }
+}
+
+impl<'a> PoseTree<'a> for DubinsRRTStar<'a> {
+    fn rrt(&self) -> &RRT<'a> {
+        &self.dubins_rrt.rrt
+    }
+
+    fn rrt_mut(&mut self) -> &mut RRT<'a> {
+        &mut self.dubins_rrt.rrt
+    }
+
+    fn start_yaw(&self) -> f32 {
+        self.dubins_rrt.start_yaw
+    }
+
+    fn goal_yaw(&self) -> f32 {
+        self.dubins_rrt.goal_yaw
+    }
+
+    fn tree(&self) -> &PathTree<PoseNode> {
+        &self.dubins_rrt.node_tree
+    }
+
+    fn tree_mut(&mut self) -> &mut PathTree<PoseNode> {
+        &mut self.dubins_rrt.node_tree
+    }
+
+    fn steer_pose(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode> {
+        self.dubins_rrt.steer(from_node, to_node, max_length, index)
+    }
+}
+
+impl<'a> PoseRRTStar<'a> for DubinsRRTStar<'a> {
+    fn connect_circle_dist(&self) -> f32 {
+        self.connect_circle_dist
+    }
+
+    fn search_until_max(&self) -> bool {
+        self.search_until_max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::ConstantVelocityObstacle;
+
+    #[test]
+    fn stops_on_a_goal_it_can_find() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (20.0, 20.0),
+        };
+        // expand_dis above one, the goal search used to look within its square root
+        for seed in 0..10 {
+            let mut planner = DubinsRRTStar::new(
+                (2.0, 2.0, 0.0),
+                (18.0, 18.0, 0.0),
+                vec![],
+                1.0,
+                3.0,
+                0.1,
+                10,
+                2000,
+                area,
+                20.0,
+                false,
+            )
+            .with_seed(seed);
+            let path = planner.plan_poses().expect("goal connection was found");
+            let end = path.0.first().unwrap();
+            assert_eq!((end.0, end.1), (18.0, 18.0));
+        }
+    }
+
+    #[test]
+    fn paths_dodge_moving_obstacles() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        // crosses the straight line while the car would drive through it
+        let shape = RectangleBounds {
+            min_pt: (-1.0, -1.0),
+            max_pt: (1.0, 1.0),
+        };
+        let crossing = ConstantVelocityObstacle::new(Box::new(shape), (5.0, 9.0), (0.0, -1.0));
+        for seed in 0..5 {
+            let mut planner = DubinsRRTStar::new(
+                (1.0, 5.0, 0.0),
+                (9.0, 5.0, 0.0),
+                vec![],
+                1.0,
+                1.0,
+                0.1,
+                10,
+                1000,
+                area,
+                20.0,
+                true,
+            )
+            .with_seed(seed);
+            planner.dubins_rrt.rrt.dynamic_obstacles = vec![&crossing];
+            assert!(planner.is_collision_path_timed(&Path2D(vec![(9.0, 5.0), (1.0, 5.0)])));
+
+            let path = planner.plan().expect("a path around the box");
+            assert!(!planner.is_collision_path_timed(&path));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Obstacles moving along predicted trajectories
+use crate::bound::Collision;
+use crate::math::{subtract, Point2D};
+use crate::path::{TimedState, Trajectory};
+
+/// obstacle whose area depends on time
+pub trait DynamicObstacle {
+    /// is the point inside the area at time t
+    fn is_collision_at(&self, pt: &Point2D, t: f32) -> bool;
+
+    /// does a point moving at constant velocity from start (at t0) to end (at t1) hit the area
+    fn is_collision_segment_at(&self, start: &Point2D, end: &Point2D, t0: f32, t1: f32) -> bool;
+}
+
+/// position of a point moving at constant velocity from start at t0 to end at t1
+fn lerp(start: &Point2D, end: &Point2D, t0: f32, t1: f32, t: f32) -> Point2D {
+    if t1 <= t0 {
+        return *start;
+    }
+    let u = (t - t0) / (t1 - t0);
+    (start.0 + u * (end.0 - start.0), start.1 + u * (end.1 - start.1))
+}
+
+/// shape translated along a piecewise linear motion, checked between the knot times
+///
+/// while both the robot and the obstacle move linearly, the robot moves along a straight
+/// segment in the frame of the shape, so every piece is an exact static segment test
+fn is_collision_swept(
+    shape: &dyn Collision,
+    position: impl Fn(f32) -> Point2D,
+    knots: impl Iterator<Item = f32>,
+    start: &Point2D,
+    end: &Point2D,
+    t0: f32,
+    t1: f32,
+) -> bool {
+    if t1 <= t0 {
+        return shape.is_collision(&subtract(start, &position(t0)));
+    }
+    let mut times = vec![t0];
+    times.extend(knots.filter(|t| *t > t0 && *t < t1));
+    times.push(t1);
+    times.windows(2).any(|w| {
+        let a = subtract(&lerp(start, end, t0, t1, w[0]), &position(w[0]));
+        let b = subtract(&lerp(start, end, t0, t1, w[1]), &position(w[1]));
+        shape.is_collision_segment(&a, &b)
+    })
+}
+
+/// shape moving at constant velocity
+///
+/// the shape is described around the origin and placed at `position` at time zero
+pub struct ConstantVelocityObstacle<'a> {
+    pub shape: Box<dyn Collision + 'a>,
+    pub position: Point2D,
+    pub velocity: Point2D,
+}
+
+impl<'a> ConstantVelocityObstacle<'a> {
+    pub fn new(shape: Box<dyn Collision + 'a>, position: Point2D, velocity: Point2D) -> Self {
+        Self {
+            shape,
+            position,
+            velocity,
+        }
+    }
+
+    /// position of the shape's origin at time t
+    pub fn position_at(&self, t: f32) -> Point2D {
+        (self.position.0 + self.velocity.0 * t, self.position.1 + self.velocity.1 * t)
+    }
+}
+
+impl DynamicObstacle for ConstantVelocityObstacle<'_> {
+    fn is_collision_at(&self, pt: &Point2D, t: f32) -> bool {
+        self.shape.is_collision(&subtract(pt, &self.position_at(t)))
+    }
+
+    fn is_collision_segment_at(&self, start: &Point2D, end: &Point2D, t0: f32, t1: f32) -> bool {
+        is_collision_swept(self.shape.as_ref(), |t| self.position_at(t), std::iter::empty(), start, end, t0, t1)
+    }
+}
+
+/// shape following a predicted trajectory
+///
+/// the shape is described around the origin and moved to the (x, y) of the trajectory,
+/// interpolated linearly between states. Before the first and after the last state it stays
+/// where the trajectory starts or ends.
+pub struct TrajectoryObstacle<'a> {
+    pub shape: Box<dyn Collision + 'a>,
+    pub trajectory: Trajectory,
+}
+
+impl<'a> TrajectoryObstacle<'a> {
+    /// None for an empty trajectory or states without a position
+    pub fn new(shape: Box<dyn Collision + 'a>, trajectory: Trajectory) -> Option<Self> {
+        if trajectory.0.is_empty() || trajectory.0.iter().any(|s| s.state.len() < 2) {
+            return None;
+        }
+        Some(Self { shape, trajectory })
+    }
+
+    /// trajectory from waypoints given as (time, position), sorted by time
+    pub fn from_waypoints(shape: Box<dyn Collision + 'a>, waypoints: &[(f32, Point2D)]) -> Option<Self> {
+        let states = waypoints
+            .iter()
+            .map(|(t, p)| TimedState {
+                t: *t,
+                state: vec![p.0, p.1],
+            })
+            .collect();
+        Self::new(shape, Trajectory(states))
+    }
+
+    /// position of the shape's origin at time t
+    pub fn position_at(&self, t: f32) -> Point2D {
+        let states = &self.trajectory.0;
+        let pos = |i: usize| (states[i].state[0], states[i].state[1]);
+        let next = states.partition_point(|s| s.t <= t);
+        if next == 0 {
+            return pos(0);
+        }
+        if next == states.len() {
+            return pos(states.len() - 1);
+        }
+        lerp(&pos(next - 1), &pos(next), states[next - 1].t, states[next].t, t)
+    }
+}
+
+impl DynamicObstacle for TrajectoryObstacle<'_> {
+    fn is_collision_at(&self, pt: &Point2D, t: f32) -> bool {
+        self.shape.is_collision(&subtract(pt, &self.position_at(t)))
+    }
+
+    fn is_collision_segment_at(&self, start: &Point2D, end: &Point2D, t0: f32, t1: f32) -> bool {
+        let knots = self.trajectory.0.iter().map(|s| s.t);
+        is_collision_swept(self.shape.as_ref(), |t| self.position_at(t), knots, start, end, t0, t1)
+    }
+}
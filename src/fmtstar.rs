@@ -0,0 +1,303 @@
+/// Fast Marching Tree (FMT*)
+use crate::bound::*;
+use crate::footprint::Footprint;
+use crate::math::*;
+use crate::path::Path2D;
+use crate::planner::Planner;
+use kiddo::{distance::squared_euclidean, KdTree};
+use crate::sampler::{Sampler, UniformSampler};
+use rand::{rngs::StdRng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// progress of a sample through the marching front
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleState {
+    Unvisited,
+    Open,
+    Closed,
+}
+
+/// open set entry, ordered so the BinaryHeap pops the lowest cost-to-come
+struct OpenItem {
+    cost: f32,
+    id: usize,
+}
+
+impl PartialEq for OpenItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for OpenItem {}
+
+impl PartialOrd for OpenItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// FMT* Configuration Object
+/// --
+///
+/// all `n_samples` samples are drawn up front, then a tree is marched out from the start in
+/// order of cost-to-come. Every unvisited sample near the front is connected to its locally
+/// best open neighbour, and only that one edge is checked for collision. The amount of work
+/// is bounded by the number of samples.
+///
+/// Only the static `obstacles` are checked, moving obstacles aren't supported.
+pub struct FMTStar<'a> {
+    pub start: Point2D,
+    pub goal: Point2D,
+    pub obstacles: Vec<&'a dyn Collision>,
+    /// robot shape checked against the obstacles, a point by default
+    pub footprint: Footprint,
+    pub explore_area: RectangleBounds,
+    pub n_samples: usize,
+    pub connect_circle_dist: f32,
+    /// upper bound of the connection radius
+    pub expand_dis: f32,
+    /// sample positions, 0 is the start and 1 the goal
+    pub nodes: Vec<Point2D>,
+    /// tree parent of every sample reached by the last plan
+    pub parents: Vec<Option<usize>>,
+    /// cost-to-come of every sample, infinite if it wasn't reached
+    pub costs: Vec<f32>,
+    /// defaults to uniform samples over the explore area
+    pub sampler: Box<dyn Sampler + 'a>,
+    kd_tree: KdTree<f32, usize, 2>,
+    rng: StdRng,
+}
+
+const START: usize = 0;
+const GOAL: usize = 1;
+
+impl Planner<'_> for FMTStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        &self.obstacles
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.footprint
+    }
+
+    fn plan(&mut self) -> Option<Path2D> {
+        if self.is_collision(&self.start) || self.is_collision(&self.goal) {
+            return None;
+        }
+        self.sample();
+        self.march()
+    }
+}
+
+impl<'a> FMTStar<'a> {
+    /// create new planner, leave other parameters open
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        explore_area: RectangleBounds,
+        n_samples: usize,
+        connect_circle_dist: f32,
+        expand_dis: f32,
+    ) -> Self {
+        Self {
+            start,
+            goal,
+            obstacles,
+            footprint: Footprint::Point,
+            explore_area,
+            n_samples,
+            connect_circle_dist,
+            expand_dis,
+            nodes: Vec::new(),
+            parents: Vec::new(),
+            costs: Vec::new(),
+            sampler: Box::new(UniformSampler::new(explore_area)),
+            kd_tree: KdTree::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// seed the random source, identical inputs and seed give identical trees and paths
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// start, goal and n_samples collision free samples
+    fn sample(&mut self) {
+        self.nodes = vec![self.start, self.goal];
+        // give up eventually if the free space is (almost) empty
+        let max_attempts = 100 * self.n_samples;
+        let mut attempts = 0;
+        while self.nodes.len() < self.n_samples + 2 && attempts < max_attempts {
+            attempts += 1;
+            let pt = self.sampler.sample(&self.goal, &mut self.rng);
+            if !self.is_collision(&pt) {
+                self.nodes.push(pt);
+            }
+        }
+
+        self.kd_tree = KdTree::new();
+        for (id, pt) in self.nodes.iter().enumerate() {
+            // duplicated points are simply left out of the neighbour search
+            let _ = self.kd_tree.add(&[pt.0, pt.1], id);
+        }
+    }
+
+    /// connection radius, shrinking with the number of samples like RRT*
+    fn radius(&self) -> f32 {
+        let n_nodes = self.nodes.len() as f32;
+        let rm = self.connect_circle_dist * (n_nodes.ln() / n_nodes).sqrt();
+        rm.min(self.expand_dis)
+    }
+
+    fn near(&self, id: usize, r: f32) -> Vec<usize> {
+        let pt = self.nodes[id];
+        match self.kd_tree.within_unsorted(&[pt.0, pt.1], r * r, &squared_euclidean) {
+            Ok(s) => s.iter().map(|(_d, idx)| **idx).filter(|idx| *idx != id).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn distance(&self, a: usize, b: usize) -> f32 {
+        euclidean_distance(&subtract(&self.nodes[a], &self.nodes[b]))
+    }
+
+    /// lazy dynamic programming expansion from the start until the goal is the cheapest open node
+    fn march(&mut self) -> Option<Path2D> {
+        let r = self.radius();
+        let n = self.nodes.len();
+        let mut states = vec![SampleState::Unvisited; n];
+        self.parents = vec![None; n];
+        self.costs = vec![f32::INFINITY; n];
+
+        let mut open = BinaryHeap::new();
+        states[START] = SampleState::Open;
+        self.costs[START] = 0.0;
+        let mut z = START;
+
+        while z != GOAL {
+            let mut new_open = Vec::new();
+            for x in self.near(z, r) {
+                if states[x] != SampleState::Unvisited {
+                    continue;
+                }
+                // locally optimal parent among the open neighbours, the only edge to check
+                let y_min = self
+                    .near(x, r)
+                    .into_iter()
+                    .filter(|y| states[*y] == SampleState::Open)
+                    .map(|y| (y, self.costs[y] + self.distance(y, x)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((y, cost)) = y_min {
+                    if !self.is_collision_segment(&self.nodes[y], &self.nodes[x]) {
+                        self.parents[x] = Some(y);
+                        self.costs[x] = cost;
+                        new_open.push(x);
+                    }
+                }
+            }
+
+            // new nodes only join the front once z is done
+            for x in new_open {
+                states[x] = SampleState::Open;
+                open.push(OpenItem {
+                    cost: self.costs[x],
+                    id: x,
+                });
+            }
+            states[z] = SampleState::Closed;
+
+            z = open.pop()?.id;
+        }
+
+        let mut path = vec![self.nodes[GOAL]];
+        let mut current = GOAL;
+        while let Some(parent) = self.parents[current] {
+            path.push(self.nodes[parent]);
+            current = parent;
+        }
+        Some(Path2D(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rrtnode::{Node, RRTNode, RRTStarNode};
+    use crate::rrtstar::RRTStar;
+
+    fn area() -> RectangleBounds {
+        RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        }
+    }
+
+    #[test]
+    fn plans_around_an_obstacle() {
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        for seed in 0..5 {
+            let mut fmt =
+                FMTStar::new((1.0, 1.0), (9.0, 9.0), vec![&circle], area(), 500, 20.0, 3.0)
+                    .with_seed(seed);
+            let path = fmt.plan().expect("a path around the circle");
+            // from the goal to the start like the other planners
+            assert_eq!(path.0.first(), Some(&(9.0, 9.0)));
+            assert_eq!(path.0.last(), Some(&(1.0, 1.0)));
+            assert!(path.0.iter().all(|pt| !fmt.is_collision(pt)));
+            assert!(path.0.windows(2).all(|w| !fmt.is_collision_segment(&w[0], &w[1])));
+        }
+    }
+
+    #[test]
+    fn walled_off_goal_has_no_path() {
+        let wall = RectangleBounds {
+            min_pt: (4.0, -1.0),
+            max_pt: (6.0, 11.0),
+        };
+        let mut fmt =
+            FMTStar::new((1.0, 1.0), (9.0, 9.0), vec![&wall], area(), 500, 20.0, 3.0).with_seed(1);
+        assert!(fmt.plan().is_none());
+        assert!(fmt.costs[GOAL].is_infinite());
+    }
+
+    #[test]
+    fn radius_matches_rrtstar() {
+        let mut fmt =
+            FMTStar::new((1.0, 1.0), (9.0, 9.0), vec![], area(), 48, 10.0, 5.0).with_seed(2);
+        fmt.sample();
+        let n = fmt.nodes.len();
+        assert!(fmt.radius() < fmt.expand_dis);
+
+        // RRT* holding every sample but the last, which is the node being inserted
+        let mut rrtstar =
+            RRTStar::new((1.0, 1.0), (9.0, 9.0), vec![], 5.0, 0.1, 5, 10, area(), 10.0, false);
+        let star_node = |id: usize| {
+            let mut node = RRTNode::new(fmt.nodes[id]);
+            node.id = id;
+            RRTStarNode { node, cost: 0.0 }
+        };
+        for id in 0..n - 1 {
+            rrtstar.node_tree.add_node(star_node(id));
+        }
+        let mut expected = rrtstar.find_near_nodes(&star_node(n - 1));
+        let mut near = fmt.near(n - 1, fmt.radius());
+        expected.sort();
+        near.sort();
+        assert!(!near.is_empty());
+        assert_eq!(near, expected);
+    }
+}
@@ -0,0 +1,196 @@
+/// Robot footprints and inflated obstacles
+use crate::bound::{Collision, RectangleBounds};
+use crate::math::{euclidean_distance, subtract, Point2D, Pose2D};
+use serde::{Deserialize, Serialize};
+
+/// shape of the robot around its reference point
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Footprint {
+    /// the robot is a point, obstacles are checked as given
+    #[default]
+    Point,
+    /// disc around the reference point
+    Disc { radius: f32 },
+    /// rectangle centered on the reference point, length along the heading
+    Rectangle { length: f32, width: f32 },
+}
+
+impl Footprint {
+    /// radius of the largest disc around the reference point inside the footprint
+    pub fn inscribed_radius(&self) -> f32 {
+        match *self {
+            Footprint::Point => 0.0,
+            Footprint::Disc { radius } => radius,
+            Footprint::Rectangle { length, width } => length.min(width) / 2.0,
+        }
+    }
+
+    /// radius of the smallest disc around the reference point containing the footprint
+    pub fn circumscribed_radius(&self) -> f32 {
+        match *self {
+            Footprint::Point => 0.0,
+            Footprint::Disc { radius } => radius,
+            Footprint::Rectangle { length, width } => euclidean_distance(&(length / 2.0, width / 2.0)),
+        }
+    }
+
+    /// robot at a point with an unknown heading
+    ///
+    /// a rectangle is checked as its circumscribed disc, which holds the footprint for every
+    /// heading. The planners check this at every node, so it also covers the robot turning
+    /// between two edges, which is_collision_segment sweeps facing straight ahead.
+    pub fn is_collision(&self, obstacle: &dyn Collision, pt: &Point2D) -> bool {
+        match *self {
+            Footprint::Point => obstacle.is_collision(pt),
+            _ => obstacle.is_collision_radius(pt, self.circumscribed_radius()),
+        }
+    }
+
+    /// robot at a pose
+    pub fn is_collision_pose(&self, obstacle: &dyn Collision, pose: &Pose2D) -> bool {
+        match *self {
+            Footprint::Rectangle { length, width } => {
+                obstacle.is_collision_convex_polygon(&rectangle_corners(pose, length, width))
+            }
+            _ => self.is_collision(obstacle, &(pose.0, pose.1)),
+        }
+    }
+
+    /// robot driving straight from start to end, facing along the segment
+    ///
+    /// a disc sweeps a capsule, a rectangle sweeps a longer rectangle
+    pub fn is_collision_segment(&self, obstacle: &dyn Collision, start: &Point2D, end: &Point2D) -> bool {
+        match *self {
+            Footprint::Point => obstacle.is_collision_segment(start, end),
+            Footprint::Disc { radius } => obstacle.is_collision_segment_radius(start, end, radius),
+            Footprint::Rectangle { length, width } => {
+                let (dx, dy) = subtract(end, start);
+                let d = euclidean_distance(&(dx, dy));
+                if d == 0.0 {
+                    return self.is_collision(obstacle, start);
+                }
+                let center = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0, dy.atan2(dx));
+                obstacle.is_collision_convex_polygon(&rectangle_corners(&center, length + d, width))
+            }
+        }
+    }
+}
+
+/// corners of a rectangle centered on the pose, counter clockwise
+fn rectangle_corners(pose: &Pose2D, length: f32, width: f32) -> [Point2D; 4] {
+    let (c, s) = (pose.2.cos(), pose.2.sin());
+    let (hl, hw) = (length / 2.0, width / 2.0);
+    [(hl, hw), (-hl, hw), (-hl, -hw), (hl, -hw)].map(|(x, y)| (pose.0 + c * x - s * y, pose.1 + s * x + c * y))
+}
+
+/// an area grown by a radius (Minkowski sum with a disc)
+///
+/// circles simply grow their radius, rectangles and polygons get rounded corners. Checking
+/// points against inflated obstacles is the same as checking a disc robot against the originals.
+pub struct InflatedBounds<'a> {
+    pub inner: &'a dyn Collision,
+    pub radius: f32,
+}
+
+impl<'a> InflatedBounds<'a> {
+    pub fn new(inner: &'a dyn Collision, radius: f32) -> Self {
+        Self { inner, radius }
+    }
+}
+
+impl Collision for InflatedBounds<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.inner.is_collision_radius(pt, self.radius)
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.inner.is_collision_segment_radius(start, end, self.radius)
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        let b = self.inner.bounding_box()?;
+        Some(RectangleBounds {
+            min_pt: (b.min_pt.0 - self.radius, b.min_pt.1 - self.radius),
+            max_pt: (b.max_pt.0 + self.radius, b.max_pt.1 + self.radius),
+        })
+    }
+
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        self.inner.signed_distance(pt) - self.radius
+    }
+
+    /// closest point of the inner boundary, pushed out by the radius
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        let cp = self.inner.closest_point(pt);
+        let mut outward = if self.inner.is_collision(pt) {
+            subtract(&cp, pt)
+        } else {
+            subtract(pt, &cp)
+        };
+        let mut d = euclidean_distance(&outward);
+        if d == 0.0 {
+            // on the inner boundary, the signed distance gradient points outward
+            let h = 1e-3;
+            outward = (
+                self.inner.signed_distance(&(pt.0 + h, pt.1)) - self.inner.signed_distance(&(pt.0 - h, pt.1)),
+                self.inner.signed_distance(&(pt.0, pt.1 + h)) - self.inner.signed_distance(&(pt.0, pt.1 - h)),
+            );
+            d = euclidean_distance(&outward);
+            if d == 0.0 {
+                return (cp.0 + self.radius, cp.1);
+            }
+        }
+        (cp.0 + self.radius * outward.0 / d, cp.1 + self.radius * outward.1 / d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bound::CircleBounds;
+    use std::f32::consts::PI;
+
+    const CAR: Footprint = Footprint::Rectangle {
+        length: 4.0,
+        width: 2.0,
+    };
+
+    #[test]
+    fn unknown_heading_covers_the_corners() {
+        let post = CircleBounds {
+            center_pt: (0.0, 0.0),
+            radius: 0.5,
+        };
+        let pt = (2.0, 0.0);
+        // 1.5 from the post, clear of the inscribed disc but not of the circumscribed one
+        assert!(!post.is_collision_radius(&pt, CAR.inscribed_radius()));
+        assert!(CAR.is_collision(&post, &pt));
+
+        // turned so that a corner points at the post
+        let heading = PI - (1.0f32).atan2(2.0);
+        assert!(CAR.is_collision_pose(&post, &(pt.0, pt.1, heading)));
+        assert!(!CAR.is_collision_pose(&post, &(pt.0, pt.1, PI / 2.0)));
+    }
+
+    #[test]
+    fn unknown_heading_is_conservative() {
+        let block = RectangleBounds {
+            min_pt: (-1.0, -1.0),
+            max_pt: (1.0, 1.0),
+        };
+        for footprint in [Footprint::Point, Footprint::Disc { radius: 0.7 }, CAR] {
+            for i in 0..=40 {
+                for j in 0..=40 {
+                    // off the grid of exact tangencies
+                    let pt = (-5.03 + 0.25 * i as f32, -4.97 + 0.25 * j as f32);
+                    let hit = (0..16).any(|k| {
+                        let heading = k as f32 * PI / 8.0;
+                        footprint.is_collision_pose(&block, &(pt.0, pt.1, heading))
+                    });
+                    let covered = footprint.is_collision(&block, &pt);
+                    assert!(!hit || covered, "{:?} at {:?}", footprint, pt);
+                }
+            }
+        }
+    }
+}
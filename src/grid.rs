@@ -0,0 +1,622 @@
+/// Occupancy grid maps, e.g. from perception or ROS map_server files
+use crate::bound::{Collision, RectangleBounds};
+use crate::math::{euclidean_distance, merge_intervals, segment_distance, subtract, Point2D};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Occupancy Grid
+/// --
+///
+/// cells are square, `resolution` meters wide, stored row major starting from the row at the
+/// origin (the lower left corner of the map). Values follow ROS: 0 is free, 100 occupied and -1
+/// unknown. Cells with a value of at least `occupied_threshold` collide, unknown cells (and
+/// everything outside the grid) collide if `unknown_is_occupied`. A cell covers
+/// [x, x + resolution) x [y, y + resolution).
+#[derive(Debug, Clone)]
+pub struct OccupancyGrid {
+    pub origin: Point2D,
+    pub resolution: f32,
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<i8>,
+    pub occupied_threshold: i8,
+    pub unknown_is_occupied: bool,
+}
+
+/// errors loading a map from disk
+#[derive(Debug)]
+pub enum MapError {
+    Io(std::io::Error),
+    Yaml(serde_yaml::Error),
+    Png(png::DecodingError),
+    /// the file content isn't a supported map
+    Format(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "cannot read map: {}", e),
+            MapError::Yaml(e) => write!(f, "invalid map metadata: {}", e),
+            MapError::Png(e) => write!(f, "invalid png map: {}", e),
+            MapError::Format(msg) => write!(f, "invalid map: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<std::io::Error> for MapError {
+    fn from(e: std::io::Error) -> Self {
+        MapError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for MapError {
+    fn from(e: serde_yaml::Error) -> Self {
+        MapError::Yaml(e)
+    }
+}
+
+impl From<png::DecodingError> for MapError {
+    fn from(e: png::DecodingError) -> Self {
+        MapError::Png(e)
+    }
+}
+
+/// map_server metadata file, the origin yaw is ignored
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapMetadata {
+    pub image: String,
+    pub resolution: f32,
+    pub origin: [f32; 3],
+    #[serde(default)]
+    pub negate: i32,
+    #[serde(default = "default_occupied_thresh")]
+    pub occupied_thresh: f32,
+    #[serde(default = "default_free_thresh")]
+    pub free_thresh: f32,
+}
+
+fn default_occupied_thresh() -> f32 {
+    0.65
+}
+
+fn default_free_thresh() -> f32 {
+    0.196
+}
+
+/// 8 bit gray image, rows from the top
+#[derive(Debug, Clone)]
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl OccupancyGrid {
+    /// None if the cell count doesn't match or the resolution isn't positive
+    pub fn new(origin: Point2D, resolution: f32, width: usize, height: usize, cells: Vec<i8>) -> Option<Self> {
+        if cells.len() != width * height || resolution <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            origin,
+            resolution,
+            width,
+            height,
+            cells,
+            occupied_threshold: 50,
+            unknown_is_occupied: true,
+        })
+    }
+
+    /// trinary interpretation of an image like map_server
+    ///
+    /// dark pixels are occupied (light ones with negate), the occupancy probability of a pixel
+    /// above occupied_thresh gives 100, below free_thresh 0 and unknown otherwise. Empty images
+    /// and pixel counts that don't match the size are format errors.
+    pub fn from_image(image: &GrayImage, meta: &MapMetadata) -> Result<Self, MapError> {
+        if image.width == 0 || image.height == 0 {
+            return Err(MapError::Format(format!("empty {}x{} image", image.width, image.height)));
+        }
+        if image.pixels.len() != image.width * image.height {
+            return Err(MapError::Format(format!(
+                "{} pixels for a {}x{} image",
+                image.pixels.len(),
+                image.width,
+                image.height
+            )));
+        }
+        let mut cells = Vec::with_capacity(image.width * image.height);
+        // the image starts at the top row, the grid at the bottom one
+        for row in image.pixels.chunks(image.width).rev() {
+            cells.extend(row.iter().map(|v| {
+                let p = if meta.negate != 0 {
+                    *v as f32 / 255.0
+                } else {
+                    (255 - *v) as f32 / 255.0
+                };
+                if p > meta.occupied_thresh {
+                    100
+                } else if p < meta.free_thresh {
+                    0
+                } else {
+                    -1
+                }
+            }));
+        }
+        Ok(Self {
+            origin: (meta.origin[0], meta.origin[1]),
+            resolution: meta.resolution,
+            width: image.width,
+            height: image.height,
+            cells,
+            occupied_threshold: 50,
+            unknown_is_occupied: true,
+        })
+    }
+
+    /// load a map_server yaml file and the PGM or PNG image it refers to
+    ///
+    /// a relative image path is relative to the yaml file
+    pub fn load_yaml(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let path = path.as_ref();
+        let meta: MapMetadata = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+        if meta.resolution <= 0.0 {
+            return Err(MapError::Format(format!("resolution {} is not positive", meta.resolution)));
+        }
+        let image_path = path.parent().unwrap_or_else(|| Path::new("")).join(&meta.image);
+        let image = match image_path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => load_png(&image_path)?,
+            _ => load_pgm(&image_path)?,
+        };
+        Self::from_image(&image, &meta)
+    }
+
+    /// cell index of a point, may be outside the grid
+    pub fn cell_index(&self, pt: &Point2D) -> (i64, i64) {
+        (
+            ((pt.0 - self.origin.0) / self.resolution).floor() as i64,
+            ((pt.1 - self.origin.1) / self.resolution).floor() as i64,
+        )
+    }
+
+    /// value of a cell, None outside the grid
+    pub fn get(&self, ix: i64, iy: i64) -> Option<i8> {
+        if ix < 0 || iy < 0 || ix >= self.width as i64 || iy >= self.height as i64 {
+            return None;
+        }
+        Some(self.cells[iy as usize * self.width + ix as usize])
+    }
+
+    pub fn is_occupied(&self, ix: i64, iy: i64) -> bool {
+        match self.get(ix, iy) {
+            Some(v) if v >= 0 => v >= self.occupied_threshold,
+            _ => self.unknown_is_occupied,
+        }
+    }
+
+    /// closed box of a cell
+    fn cell_box(&self, ix: i64, iy: i64) -> RectangleBounds {
+        let min_pt = (
+            self.origin.0 + ix as f32 * self.resolution,
+            self.origin.1 + iy as f32 * self.resolution,
+        );
+        RectangleBounds {
+            min_pt,
+            max_pt: (min_pt.0 + self.resolution, min_pt.1 + self.resolution),
+        }
+    }
+
+    /// cells visited by the segment in order (Amanatides-Woo traversal)
+    ///
+    /// exact for half open cells, passing through a corner steps diagonally
+    pub fn traverse(&self, start: &Point2D, end: &Point2D) -> Vec<(i64, i64)> {
+        let (mut ix, mut iy) = self.cell_index(start);
+        let (ex, ey) = self.cell_index(end);
+        let dir = subtract(end, start);
+        let axis = |s: f32, d: f32, i: i64, o: f32| -> (i64, f32, f32) {
+            if d > 0.0 {
+                let next = o + (i + 1) as f32 * self.resolution;
+                (1, (next - s) / d, self.resolution / d)
+            } else if d < 0.0 {
+                let next = o + i as f32 * self.resolution;
+                (-1, (next - s) / d, -self.resolution / d)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (step_x, mut t_max_x, t_delta_x) = axis(start.0, dir.0, ix, self.origin.0);
+        let (step_y, mut t_max_y, t_delta_y) = axis(start.1, dir.1, iy, self.origin.1);
+
+        let mut cells = vec![(ix, iy)];
+        // the cell count bounds the loop against rounding at the end cell
+        let max_steps = (ex - ix).abs() + (ey - iy).abs();
+        for _ in 0..max_steps {
+            if (ix, iy) == (ex, ey) || t_max_x.min(t_max_y) > 1.0 {
+                break;
+            }
+            if t_max_x < t_max_y {
+                ix += step_x;
+                t_max_x += t_delta_x;
+            } else if t_max_y < t_max_x {
+                iy += step_y;
+                t_max_y += t_delta_y;
+            } else {
+                ix += step_x;
+                iy += step_y;
+                t_max_x += t_delta_x;
+                t_max_y += t_delta_y;
+            }
+            cells.push((ix, iy));
+        }
+        cells
+    }
+
+    /// closest cell box of the wanted occupancy, searched in growing rings of cells
+    ///
+    /// every ring is at least (k - 1) cells away, so the search stops once that exceeds the
+    /// best distance. Rings beyond the grid only hold unknown cells, the search ends there.
+    fn nearest_cell(&self, pt: &Point2D, occupied: bool) -> Option<(f32, Point2D)> {
+        let (cx, cy) = self.cell_index(pt);
+        let max_ring = [
+            cx.abs(),
+            cy.abs(),
+            (self.width as i64 - cx).abs(),
+            (self.height as i64 - cy).abs(),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+            + 1;
+
+        let mut best: Option<(f32, Point2D)> = None;
+        for k in 0..=max_ring {
+            if let Some((d, _)) = best {
+                if (k - 1) as f32 * self.resolution > d {
+                    break;
+                }
+            }
+            let ring = (-k..=k)
+                .flat_map(|dx| [(dx, -k), (dx, k)])
+                .chain((-k + 1..k).flat_map(|dy| [(-k, dy), (k, dy)]));
+            for (dx, dy) in ring {
+                let (ix, iy) = (cx + dx, cy + dy);
+                if self.is_occupied(ix, iy) != occupied {
+                    continue;
+                }
+                let cell = self.cell_box(ix, iy);
+                let p = (
+                    pt.0.clamp(cell.min_pt.0, cell.max_pt.0),
+                    pt.1.clamp(cell.min_pt.1, cell.max_pt.1),
+                );
+                let d = euclidean_distance(&subtract(pt, &p));
+                if best.is_none_or(|(bd, _)| d < bd) {
+                    best = Some((d, p));
+                }
+            }
+        }
+        best
+    }
+}
+
+/// distance between a segment and a closed box
+fn segment_box_distance(start: &Point2D, end: &Point2D, b: &RectangleBounds) -> f32 {
+    let inside = |p: &Point2D| p.0 >= b.min_pt.0 && p.0 <= b.max_pt.0 && p.1 >= b.min_pt.1 && p.1 <= b.max_pt.1;
+    if inside(start) || inside(end) {
+        return 0.0;
+    }
+    let corners = [
+        b.min_pt,
+        (b.max_pt.0, b.min_pt.1),
+        b.max_pt,
+        (b.min_pt.0, b.max_pt.1),
+    ];
+    (0..4)
+        .map(|i| segment_distance(start, end, &corners[i], &corners[(i + 1) % 4]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+impl Collision for OccupancyGrid {
+    /// constant time cell lookup
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        let (ix, iy) = self.cell_index(pt);
+        self.is_occupied(ix, iy)
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.traverse(start, end)
+            .into_iter()
+            .any(|(ix, iy)| self.is_occupied(ix, iy))
+    }
+
+    /// slab intervals of the occupied cells along the traversal
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let dir = subtract(end, start);
+        let intervals = self
+            .traverse(start, end)
+            .into_iter()
+            .filter(|(ix, iy)| self.is_occupied(*ix, *iy))
+            .filter_map(|(ix, iy)| {
+                let cell = self.cell_box(ix, iy);
+                let (mut lo, mut hi) = (0.0f32, 1.0f32);
+                for (s, d, min, max) in [
+                    (start.0, dir.0, cell.min_pt.0, cell.max_pt.0),
+                    (start.1, dir.1, cell.min_pt.1, cell.max_pt.1),
+                ] {
+                    if d != 0.0 {
+                        let (ta, tb) = ((min - s) / d, (max - s) / d);
+                        lo = lo.max(ta.min(tb));
+                        hi = hi.min(ta.max(tb));
+                    }
+                }
+                (lo <= hi).then_some((lo, hi))
+            })
+            .collect();
+        merge_intervals(intervals)
+    }
+
+    /// exact against every cell near the segment
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        if self.is_collision_segment(start, end) {
+            return true;
+        }
+        let lo = self.cell_index(&(start.0.min(end.0) - radius, start.1.min(end.1) - radius));
+        let hi = self.cell_index(&(start.0.max(end.0) + radius, start.1.max(end.1) + radius));
+        (lo.1..=hi.1).any(|iy| {
+            (lo.0..=hi.0).any(|ix| {
+                self.is_occupied(ix, iy) && segment_box_distance(start, end, &self.cell_box(ix, iy)) < radius
+            })
+        })
+    }
+
+    /// the grid extent, unbounded if unknown space collides
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        if self.unknown_is_occupied {
+            return None;
+        }
+        Some(RectangleBounds {
+            min_pt: self.origin,
+            max_pt: (
+                self.origin.0 + self.width as f32 * self.resolution,
+                self.origin.1 + self.height as f32 * self.resolution,
+            ),
+        })
+    }
+
+    /// distance to the nearest cell of the other occupancy, infinite if there is none
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        let inside = self.is_collision(pt);
+        match self.nearest_cell(pt, !inside) {
+            Some((d, _)) if inside => -d,
+            Some((d, _)) => d,
+            None if inside => f32::NEG_INFINITY,
+            None => f32::INFINITY,
+        }
+    }
+
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        self.nearest_cell(pt, !self.is_collision(pt))
+            .map_or(*pt, |(_, p)| p)
+    }
+}
+
+/// read a binary (P5) or ascii (P2) PGM image, scaled to 8 bits
+pub fn load_pgm(path: impl AsRef<Path>) -> Result<GrayImage, MapError> {
+    let data = fs::read(path)?;
+    let format_err = |msg: &str| MapError::Format(format!("pgm: {}", msg));
+
+    // header: magic, width, height and maxval, separated by whitespace, # starts a comment
+    let mut pos = 0;
+    let mut token = || -> Option<String> {
+        loop {
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < data.len() && data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = pos;
+        while pos < data.len() && !data[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        (pos > start).then(|| String::from_utf8_lossy(&data[start..pos]).into_owned())
+    };
+    let magic = token().ok_or_else(|| format_err("empty file"))?;
+    let mut number = |name: &str| -> Result<usize, MapError> {
+        token()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| format_err(&format!("bad {}", name)))
+    };
+    let width = number("width")?;
+    let height = number("height")?;
+    let maxval = number("maxval")?;
+    if maxval == 0 || maxval > 65535 {
+        return Err(format_err("bad maxval"));
+    }
+    let scale = |v: usize| {
+        if v > maxval {
+            Err(format_err("pixel above maxval"))
+        } else {
+            Ok((v * 255 / maxval) as u8)
+        }
+    };
+
+    let count = width * height;
+    let pixels: Vec<u8> = match magic.as_str() {
+        "P5" => {
+            // a single whitespace byte separates the header from the raster
+            let raster = &data[(pos + 1).min(data.len())..];
+            let bytes = if maxval < 256 { 1 } else { 2 };
+            if raster.len() < count * bytes {
+                return Err(format_err("truncated raster"));
+            }
+            (0..count)
+                .map(|i| {
+                    if bytes == 1 {
+                        scale(raster[i] as usize)
+                    } else {
+                        scale(((raster[2 * i] as usize) << 8) | raster[2 * i + 1] as usize)
+                    }
+                })
+                .collect::<Result<_, _>>()?
+        }
+        "P2" => (0..count)
+            .map(|_| number("pixel").and_then(scale))
+            .collect::<Result<_, _>>()?,
+        _ => return Err(format_err("only P2 and P5 images are supported")),
+    };
+    Ok(GrayImage { width, height, pixels })
+}
+
+/// read a PNG image as 8 bit gray, color channels are averaged and alpha is ignored
+pub fn load_png(path: impl AsRef<Path>) -> Result<GrayImage, MapError> {
+    let mut decoder = png::Decoder::new(fs::File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let channels = info.color_type.samples();
+    let color = match info.color_type {
+        png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => 1,
+        _ => 3,
+    };
+    let pixels = buf[..info.buffer_size()]
+        .chunks(channels)
+        .map(|px| (px[..color].iter().map(|v| *v as u32).sum::<u32>() / color as u32) as u8)
+        .collect();
+    Ok(GrayImage {
+        width: info.width as usize,
+        height: info.height as usize,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// fresh directory for the fixture files of a test
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("rrt_merci_grid_{}_{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_png(path: &Path, width: u32, height: u32, color: png::ColorType, data: &[u8]) {
+        let mut encoder = png::Encoder::new(fs::File::create(path).unwrap(), width, height);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(data).unwrap();
+    }
+
+    fn meta(image: &str) -> MapMetadata {
+        MapMetadata {
+            image: image.to_string(),
+            resolution: 0.5,
+            origin: [1.0, 2.0, 0.0],
+            negate: 0,
+            occupied_thresh: default_occupied_thresh(),
+            free_thresh: default_free_thresh(),
+        }
+    }
+
+    #[test]
+    fn pgm_ascii_and_binary_agree() {
+        let dir = fixture_dir("pgm");
+        let ascii = "P2\n# a comment\n3 2\n255\n0 128 255\n255 0 64\n";
+        fs::write(dir.join("ascii.pgm"), ascii).unwrap();
+        let mut binary = b"P5\n3 2\n255\n".to_vec();
+        binary.extend([0, 128, 255, 255, 0, 64]);
+        fs::write(dir.join("binary.pgm"), binary).unwrap();
+
+        for file in ["ascii.pgm", "binary.pgm"] {
+            let image = load_pgm(dir.join(file)).unwrap();
+            assert_eq!((image.width, image.height), (3, 2));
+            assert_eq!(image.pixels, vec![0, 128, 255, 255, 0, 64]);
+        }
+    }
+
+    #[test]
+    fn pgm_wide_maxval_is_scaled() {
+        let dir = fixture_dir("pgm16");
+        fs::write(dir.join("ascii.pgm"), "P2 3 1 1000 0 500 1000").unwrap();
+        let mut binary = b"P5 3 1 1000\n".to_vec();
+        for v in [0u16, 500, 1000] {
+            binary.extend(v.to_be_bytes());
+        }
+        fs::write(dir.join("binary.pgm"), binary).unwrap();
+
+        for file in ["ascii.pgm", "binary.pgm"] {
+            assert_eq!(load_pgm(dir.join(file)).unwrap().pixels, vec![0, 127, 255]);
+        }
+        fs::write(dir.join("above.pgm"), "P2 2 1 1000 0 1001").unwrap();
+        assert!(matches!(load_pgm(dir.join("above.pgm")), Err(MapError::Format(_))));
+    }
+
+    #[test]
+    fn png_channels_are_averaged() {
+        let dir = fixture_dir("png");
+        write_png(&dir.join("gray.png"), 2, 1, png::ColorType::Grayscale, &[10, 200]);
+        write_png(&dir.join("rgb.png"), 2, 1, png::ColorType::Rgb, &[0, 30, 60, 255, 255, 0]);
+
+        assert_eq!(load_png(dir.join("gray.png")).unwrap().pixels, vec![10, 200]);
+        let rgb = load_png(dir.join("rgb.png")).unwrap();
+        assert_eq!((rgb.width, rgb.height), (2, 1));
+        assert_eq!(rgb.pixels, vec![30, 170]);
+    }
+
+    #[test]
+    fn yaml_thresholds_and_negate() {
+        let dir = fixture_dir("yaml");
+        // top row black, gray, white; bottom row white, white, black
+        fs::write(dir.join("map.pgm"), "P2 3 2 255 0 128 255 255 255 0").unwrap();
+        let pixels = [0, 128, 255, 255, 255, 0];
+        write_png(&dir.join("map.png"), 3, 2, png::ColorType::Grayscale, &pixels);
+        let yaml = |image: &str, extra: &str| {
+            format!("image: {}\nresolution: 0.5\norigin: [1.0, 2.0, 0.0]\n{}", image, extra)
+        };
+        let cases = [
+            // the grid starts at the bottom row, gray is unknown
+            ("map.pgm", "", vec![0, 0, 100, 100, -1, 0]),
+            ("map.png", "", vec![0, 0, 100, 100, -1, 0]),
+            ("map.pgm", "negate: 1\n", vec![100, 100, 0, 0, -1, 100]),
+            ("map.pgm", "occupied_thresh: 0.4\n", vec![0, 0, 100, 100, 100, 0]),
+            ("map.pgm", "free_thresh: 0.6\n", vec![0, 0, 100, 100, 0, 0]),
+        ];
+        for (image, extra, cells) in cases {
+            fs::write(dir.join("map.yaml"), yaml(image, extra)).unwrap();
+            let grid = OccupancyGrid::load_yaml(dir.join("map.yaml")).unwrap();
+            assert_eq!((grid.width, grid.height, grid.resolution), (3, 2, 0.5));
+            assert_eq!(grid.origin, (1.0, 2.0));
+            assert_eq!(grid.cells, cells, "{image} {extra}");
+        }
+    }
+
+    #[test]
+    fn empty_images_are_errors() {
+        let empty = GrayImage {
+            width: 0,
+            height: 3,
+            pixels: vec![],
+        };
+        let grid = OccupancyGrid::from_image(&empty, &meta("empty.pgm"));
+        assert!(matches!(grid, Err(MapError::Format(_))));
+
+        let dir = fixture_dir("empty");
+        fs::write(dir.join("map.pgm"), "P5 0 0 255\n").unwrap();
+        let yaml = "image: map.pgm\nresolution: 0.5\norigin: [0.0, 0.0, 0.0]\n";
+        fs::write(dir.join("map.yaml"), yaml).unwrap();
+        assert!(matches!(OccupancyGrid::load_yaml(dir.join("map.yaml")), Err(MapError::Format(_))));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::bound::{Collision, RectangleBounds};
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::math::Point2D;
+use crate::path::Path2D;
+use crate::planner::Planner;
+/// Informed Rapidly Exploring Random Tree Star
+use crate::rrtnode::Node;
+use crate::rrtnode::{RRTNode, RRTStarNode};
+use crate::rrtstar::RRTStar;
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// RRT* that focuses its sampling once a solution is known
+/// --
+///
+/// before the goal is reached, samples come from the RRT sampler. Afterwards, samples are
+/// drawn from the prolate hyperspheroid (an ellipse in 2D) with foci at the start and goal whose
+/// transverse diameter is the current best cost--only points in it can improve the solution.
+pub struct InformedRRTStar<'a> {
+    pub rrtstar: RRTStar<'a>,
+    /// (iteration, cost) recorded every time the best solution cost improves
+    pub cost_history: Vec<(u32, f32)>,
+}
+
+impl Planner<'_> for InformedRRTStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrtstar.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrtstar.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrtstar.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrtstar.nominal_speed()
+    }
+
+    fn plan(&mut self) -> Option<Path2D> {
+        let start_node = RRTNode::new(self.rrtstar.rrt.start);
+        let end_node = RRTNode::new(self.rrtstar.rrt.goal);
+        self.rrtstar.node_tree.add_node(RRTStarNode {
+            node: start_node,
+            cost: 0.0,
+        });
+        self.cost_history.clear();
+
+        // identifiers match insertion order (see RRTStar::plan)
+        let mut push_idx = 1;
+        let mut best_cost = f32::INFINITY;
+
+        for iter in 1..=self.rrtstar.rrt.max_iter {
+            let rnd_node = if best_cost.is_finite() {
+                // the ellipse can poke out of the explore area, those samples are wasted
+                let pt = self.sample_informed(best_cost);
+                if !self.rrtstar.rrt.explore_area.is_collision(&pt) {
+                    continue;
+                }
+                RRTStarNode::new(pt)
+            } else {
+                self.rrtstar.get_random_node(&end_node)
+            };
+            let nearest_ind = self
+                .rrtstar
+                .node_tree
+                .get_nearest_node_index(&rnd_node)
+                .expect("node list should have a size > 0");
+            let nearest_node = self
+                .rrtstar
+                .node_tree
+                .get(nearest_ind)
+                .expect("RRT Nearest Node failed to get from node list");
+
+            let new_node_r = self.rrtstar.rrt.steer(
+                &nearest_node.node,
+                &rnd_node.node,
+                self.rrtstar.rrt.expand_dis,
+                push_idx,
+            );
+            let cost_r = nearest_node.cost + new_node_r.distance_between(&nearest_node.node);
+            let new_node = RRTStarNode {
+                node: new_node_r,
+                cost: cost_r,
+            };
+            let near_goal = new_node.distance_between_pos(self.rrtstar.rrt.goal)
+                <= self.rrtstar.rrt.expand_dis;
+
+            let edge_collision_occured = self.rrtstar.is_collision_parent(&new_node);
+            if !self.is_collision(&new_node.node.point) && !edge_collision_occured {
+                let near_inds = self.rrtstar.find_near_nodes(&new_node);
+                match self.rrtstar.choose_parent(&new_node, &near_inds, push_idx) {
+                    Some(node_p) => {
+                        self.rrtstar.rewire(&node_p, &near_inds);
+                        self.rrtstar.node_tree.add_node(node_p);
+                    }
+                    None => self.rrtstar.node_tree.add_node(new_node),
+                }
+                push_idx += 1;
+
+                // rewiring may have lowered the cost of an existing goal node, so look again
+                if best_cost.is_finite() || near_goal {
+                    let cost = self.best_goal_cost();
+                    if cost < best_cost {
+                        best_cost = cost;
+                        self.cost_history.push((iter, cost));
+                    }
+                }
+            }
+
+            if !self.rrtstar.search_until_max && best_cost.is_finite() {
+                break;
+            }
+        }
+
+        let last_index = self.best_goal_node()?;
+        Some(Path2D(self.rrtstar.node_tree.get_path(
+            self.rrtstar.node_tree.get(last_index).unwrap(),
+            Vec::<Point2D>::new(),
+        )))
+    }
+}
+
+impl<'a> InformedRRTStar<'a> {
+    /// create new tree, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    ) -> Self {
+        Self {
+            rrtstar: RRTStar::new(
+                start,
+                goal,
+                obstacles,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+                connect_circle_dist,
+                search_until_max,
+            ),
+            cost_history: Vec::new(),
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrtstar = self.rrtstar.with_seed(seed);
+        self
+    }
+
+    /// node near the goal with the cheapest path once the final leg to the goal is added
+    pub fn best_goal_node(&self) -> Option<usize> {
+        self.rrtstar
+            .safe_goal_inds()
+            .into_iter()
+            .map(|idx| (self.goal_cost(idx), idx))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_cost, idx)| idx)
+    }
+
+    /// cost of the best path found so far (node cost plus the final leg to the goal)
+    pub fn best_goal_cost(&self) -> f32 {
+        self.best_goal_node()
+            .map_or(f32::INFINITY, |idx| self.goal_cost(idx))
+    }
+
+    fn goal_cost(&self, idx: usize) -> f32 {
+        let node = self.rrtstar.node_tree.get(idx).unwrap();
+        node.cost + node.distance_between_pos(self.rrtstar.rrt.goal)
+    }
+
+    /// uniform sample inside the ellipse of paths from start to goal no longer than c_best
+    pub fn sample_informed(&mut self, c_best: f32) -> Point2D {
+        sample_informed(
+            &self.rrtstar.rrt.start,
+            &self.rrtstar.rrt.goal,
+            c_best,
+            &mut self.rrtstar.rrt.rng,
+        )
+    }
+}
+
+/// uniform sample inside the ellipse of points x with |x - start| + |x - goal| <= c_best
+pub fn sample_informed<R: Rng + ?Sized>(
+    start: &Point2D,
+    goal: &Point2D,
+    c_best: f32,
+    rng: &mut R,
+) -> Point2D {
+    let (dx, dy) = (goal.0 - start.0, goal.1 - start.1);
+    let c_min = (dx * dx + dy * dy).sqrt();
+    let theta = dy.atan2(dx);
+    let center = ((start.0 + goal.0) / 2.0, (start.1 + goal.1) / 2.0);
+
+    // semi axes of the ellipse, the minor one collapses as c_best approaches c_min
+    let r1 = c_best / 2.0;
+    let r2 = (c_best * c_best - c_min * c_min).max(0.0).sqrt() / 2.0;
+
+    // uniform in the unit ball, then stretch, rotate and translate
+    let r = rng.gen_range(0.0f32..1.0).sqrt();
+    let phi = rng.gen_range(0.0..(2.0 * PI));
+    let (x, y) = (r1 * r * phi.cos(), r2 * r * phi.sin());
+    (
+        center.0 + x * theta.cos() - y * theta.sin(),
+        center.1 + x * theta.sin() + y * theta.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bound::CircleBounds;
+    use crate::math::{euclidean_distance, subtract};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn samples_stay_in_the_ellipse() {
+        let (start, goal) = ((1.0, 2.0), (7.0, 5.0));
+        let c_min = euclidean_distance(&subtract(&goal, &start));
+        let mut rng = StdRng::seed_from_u64(7);
+        // at c_min the ellipse collapses onto the segment between the foci
+        for c_best in [c_min, c_min * 1.2, c_min * 3.0] {
+            for _ in 0..1000 {
+                let pt = sample_informed(&start, &goal, c_best, &mut rng);
+                let sum = euclidean_distance(&subtract(&pt, &start))
+                    + euclidean_distance(&subtract(&pt, &goal));
+                assert!(sum <= c_best + 1e-4, "{:?} sums to {} > {}", pt, sum, c_best);
+            }
+        }
+    }
+
+    #[test]
+    fn cost_history_never_increases() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        for seed in 0..3 {
+            let (start, goal) = ((1.0, 1.0), (9.0, 9.0));
+            let mut planner =
+                InformedRRTStar::new(start, goal, vec![&circle], 0.5, 0.1, 5, 1500, area, 5.0, true)
+                    .with_seed(seed);
+            assert!(planner.plan().is_some());
+            let history = &planner.cost_history;
+            assert!(!history.is_empty());
+            assert!(history.windows(2).all(|w| w[0].0 < w[1].0 && w[1].1 < w[0].1));
+            assert_eq!(history.last().unwrap().1, planner.best_goal_cost());
+        }
+    }
+
+    #[test]
+    fn best_goal_counts_the_final_leg() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let mut planner =
+            InformedRRTStar::new((0.0, 0.0), (10.0, 0.0), vec![], 4.0, 0.1, 5, 10, area, 5.0, true);
+        let node = |id, parent_id, point, cost| RRTStarNode {
+            node: RRTNode {
+                id,
+                parent_id,
+                point,
+                path: vec![],
+            },
+            cost,
+        };
+        let tree = &mut planner.rrtstar.node_tree;
+        tree.add_node(node(0, None, (0.0, 0.0), 0.0));
+        // 8.0 + 2.0 to the goal
+        tree.add_node(node(1, Some(0), (8.0, 0.0), 8.0));
+        // cheaper node, but 7.9 + 3.18 to the goal
+        tree.add_node(node(2, Some(0), (7.2, 1.5), 7.9));
+
+        assert_eq!(planner.best_goal_node(), Some(1));
+        assert_eq!(planner.best_goal_cost(), 10.0);
+    }
+}
@@ -0,0 +1,253 @@
+/// Tree search over poses, shared by the Dubins and Reeds-Shepp planners
+use crate::bound::*;
+use crate::math::*;
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::{PoseNode, RRTNode};
+use crate::tree::PathTree;
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// length of the sampled curve
+fn poses_length(poses: &[Pose2D]) -> f32 {
+    poses
+        .windows(2)
+        .map(|w| euclidean_distance(&subtract(&(w[1].0, w[1].1), &(w[0].0, w[0].1))))
+        .sum()
+}
+
+/// Pose Tree
+/// --
+///
+/// a tree of poses joined by steering curves. The kinematic planners only differ in
+/// `steer_pose`, sampling and the edge checks are written once here.
+pub(crate) trait PoseTree<'a>: Planner<'a> {
+    fn rrt(&self) -> &RRT<'a>;
+    fn rrt_mut(&mut self) -> &mut RRT<'a>;
+    fn start_yaw(&self) -> f32;
+    fn goal_yaw(&self) -> f32;
+    fn tree(&self) -> &PathTree<PoseNode>;
+    fn tree_mut(&mut self) -> &mut PathTree<PoseNode>;
+
+    /// follow the steering curve from node to node for at most max_length of arc
+    ///
+    /// the new node takes the pose where the curve was cut off. None if no curve exists.
+    fn steer_pose(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode>;
+
+    fn start_node(&self) -> PoseNode {
+        let mut start_node = PoseNode::new(self.rrt().start);
+        start_node.yaw = self.start_yaw();
+        start_node.poses.push(start_node.pose());
+        start_node
+    }
+
+    fn goal_node(&self) -> PoseNode {
+        let mut goal_node = PoseNode::new(self.rrt().goal);
+        goal_node.yaw = self.goal_yaw();
+        goal_node
+    }
+
+    /// random pose (exploration), the goal sampling rate picks the goal pose instead
+    fn random_pose_node(&mut self) -> PoseNode {
+        let goal = self.rrt().goal;
+        let rnd = self.rrt_mut().get_random_node(&RRTNode::new(goal));
+        if rnd.point == goal {
+            return self.goal_node();
+        }
+        let mut node = PoseNode::new(rnd.point);
+        node.yaw = self.rrt_mut().rng.gen_range(-PI..PI);
+        node
+    }
+
+    /// check the sampled edge curve: every pose must be inside the explore area, and
+    /// every chord between consecutive poses free of obstacles
+    fn is_collision_edge(&self, poses: &[Pose2D]) -> bool {
+        poses.iter().any(|pose| {
+            !self.rrt().explore_area.is_collision(&(pose.0, pose.1)) || self.is_collision_pose(pose)
+        }) || poses
+            .windows(2)
+            .any(|w| self.is_collision_segment(&(w[0].0, w[0].1), &(w[1].0, w[1].1)))
+    }
+
+    /// length of the curves from the root to a node (the cost may weigh them differently)
+    fn arc_length(&self, id: usize) -> f32 {
+        let mut length = 0.0;
+        let mut node = self.tree().get(id);
+        while let Some(n) = node {
+            length += poses_length(&n.poses);
+            node = n.parent_id().and_then(|p| self.tree().get(p));
+        }
+        length
+    }
+
+    /// check the sampled edge curve against the moving obstacles, driven from arc_length on
+    fn is_collision_edge_timed(&self, poses: &[Pose2D], arc_length: f32) -> bool {
+        let mut arc_length = arc_length;
+        poses.windows(2).any(|w| {
+            let (a, b) = ((w[0].0, w[0].1), (w[1].0, w[1].1));
+            let collision = self.is_collision_segment_timed(&a, &b, arc_length);
+            arc_length += euclidean_distance(&subtract(&b, &a));
+            collision
+        })
+    }
+
+    /// check the edge from the node's parent, moving obstacles see it after the parent's arc length
+    fn is_collision_node(&self, node: &PoseNode) -> bool {
+        self.is_collision_edge(&node.poses)
+            || (!self.dynamic_obstacles().is_empty()
+                && self.is_collision_edge_timed(
+                    &node.poses,
+                    node.parent_id().map_or(0.0, |p| self.arc_length(p)),
+                ))
+    }
+
+    /// check the edges below a node against the moving obstacles, the node reached after arc_length
+    fn is_collision_subtree_timed(&self, parent_id: usize, arc_length: f32) -> bool {
+        self.tree()
+            .node_list()
+            .into_iter()
+            .filter(|n| n.parent_id() == Some(parent_id))
+            .any(|child| {
+                self.is_collision_edge_timed(&child.poses, arc_length)
+                    || self.is_collision_subtree_timed(
+                        child.id(),
+                        arc_length + poses_length(&child.poses),
+                    )
+            })
+    }
+
+    /// full curve from a tree node into the goal pose, if it is collision free
+    fn connect_goal(&self, from_id: usize, index: usize) -> Option<PoseNode> {
+        let from_node = self.tree().get(from_id)?;
+        let goal_node = self.steer_pose(from_node, &self.goal_node(), f32::INFINITY, index)?;
+        if self.is_collision_node(&goal_node) {
+            None
+        } else {
+            Some(goal_node)
+        }
+    }
+}
+
+/// RRT* over a pose tree (two more hyperparameters)
+pub(crate) trait PoseRRTStar<'a>: PoseTree<'a> {
+    fn connect_circle_dist(&self) -> f32;
+    fn search_until_max(&self) -> bool;
+
+    /// grow the tree, the goal node (not added to the tree) of the cheapest path found
+    fn plan_goal_node(&mut self) -> Option<PoseNode> {
+        let start_node = self.start_node();
+        self.tree_mut().add_node(start_node);
+        let expand_dis = self.rrt().expand_dis;
+        let mut push_idx = 1;
+
+        for _idx in 1..=self.rrt().max_iter {
+            let rnd_node = self.random_pose_node();
+            let nearest_ind = self
+                .tree()
+                .get_nearest_node_index(&rnd_node)
+                .expect("node list should have a size > 0");
+            let nearest_node = self
+                .tree()
+                .get(nearest_ind)
+                .expect("RRT Nearest Node failed to get from node list");
+            let new_node = match self.steer_pose(nearest_node, &rnd_node, expand_dis, push_idx) {
+                Some(n) if !self.is_collision_node(&n) => n,
+                _ => continue,
+            };
+
+            let near_inds = self.find_near_nodes(&new_node);
+            let new_node = self.choose_parent(new_node, &near_inds);
+            let near_goal = new_node.distance_between_pos(self.rrt().goal) <= expand_dis;
+            self.tree_mut().add_node(new_node.clone());
+            self.rewire(&new_node, &near_inds);
+            push_idx += 1;
+
+            if !self.search_until_max()
+                && near_goal
+                && self.connect_goal(new_node.id(), push_idx).is_some()
+            {
+                break;
+            }
+        }
+
+        self.search_best_goal_node(push_idx)
+    }
+
+    fn find_near_nodes(&self, new_node: &PoseNode) -> Vec<usize> {
+        let n_nodes = (self.tree().len() + 1) as f32;
+        let rm = self.connect_circle_dist() * (n_nodes.ln() / n_nodes).sqrt();
+        self.tree().get_within(new_node, rm.min(self.rrt().expand_dis))
+    }
+
+    /// reconnect the new node to the near node that reaches its pose the cheapest
+    fn choose_parent(&self, new_node: PoseNode, near_inds: &[usize]) -> PoseNode {
+        let mut best = new_node;
+        for idx in near_inds {
+            let near_node = self.tree().get(*idx).unwrap();
+            if let Some(t_node) = self.steer_pose(near_node, &best, f32::INFINITY, best.id()) {
+                if t_node.cost < best.cost && !self.is_collision_node(&t_node) {
+                    best = t_node;
+                }
+            }
+        }
+        best
+    }
+
+    /// route near nodes through the new node when that makes them cheaper
+    ///
+    /// with moving obstacles the subtree below a rewired node must stay clear at its new times
+    fn rewire(&mut self, new_node: &PoseNode, near_inds: &[usize]) {
+        for idx in near_inds {
+            let near_node = self.tree().get(*idx).unwrap();
+            if let Some(t_node) = self.steer_pose(new_node, near_node, f32::INFINITY, *idx) {
+                if t_node.cost < near_node.cost
+                    && !self.is_collision_node(&t_node)
+                    && (self.dynamic_obstacles().is_empty()
+                        || !self.is_collision_subtree_timed(
+                            *idx,
+                            self.arc_length(new_node.id()) + poses_length(&t_node.poses),
+                        ))
+                {
+                    let delta = t_node.cost - near_node.cost;
+                    self.tree_mut().set(t_node);
+                    self.propagate_cost_to_leaves(*idx, delta);
+                }
+            }
+        }
+    }
+
+    /// the edges below a rewired node are unchanged, their cost shifts by the same delta
+    fn propagate_cost_to_leaves(&mut self, parent_id: usize, delta: f32) {
+        let children: Vec<PoseNode> = self
+            .tree()
+            .node_list()
+            .into_iter()
+            .filter(|n| n.parent_id() == Some(parent_id))
+            .cloned()
+            .collect();
+        for mut child in children {
+            child.cost += delta;
+            let child_id = child.id();
+            self.tree_mut().set(child);
+            self.propagate_cost_to_leaves(child_id, delta);
+        }
+    }
+
+    /// cheapest collision free connection from a node near the goal into the goal pose
+    fn search_best_goal_node(&self, index: usize) -> Option<PoseNode> {
+        let goal_inds = self
+            .tree()
+            .get_within(&PoseNode::new(self.rrt().goal), self.rrt().expand_dis);
+        goal_inds
+            .iter()
+            .filter_map(|idx| self.connect_goal(*idx, index))
+            .min_by(|a, b| a.cost.total_cmp(&b.cost))
+    }
+}
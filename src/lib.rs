@@ -2,20 +2,57 @@
 /// Make a better way to describe obstacles
 /// TODO: implement RRT* and others...
 /// TODO: add methods to node (distance between) and obstacles (does collide)
+mod kinematic;
 mod rrtnode;
 pub use rrtnode::*;
 
+pub mod bitstar;
 pub mod bound;
+pub mod csg;
+pub mod dubins;
+pub mod dubinsrrt;
+pub mod dynamic;
+pub mod fmtstar;
+pub mod footprint;
+pub mod grid;
+pub mod informedrrtstar;
+pub mod lqr;
+pub mod lqrrrtstar;
 pub mod math;
 pub mod path;
 pub mod planner;
+pub mod prm;
+pub mod reedsshepp;
+pub mod render;
 pub mod rrt;
+pub mod rrtconnect;
 pub mod rrtstar;
+pub mod rrtx;
+pub mod sampler;
+pub mod scene;
+pub mod world;
 
+pub use bitstar::BITStar;
 pub use bound::*;
+pub use csg::{Complement, Difference, Intersection, Union};
+pub use dubinsrrt::{DubinsRRT, DubinsRRTStar};
+pub use dynamic::{ConstantVelocityObstacle, DynamicObstacle, TrajectoryObstacle};
+pub use fmtstar::FMTStar;
+pub use footprint::{Footprint, InflatedBounds};
+pub use grid::OccupancyGrid;
+pub use informedrrtstar::InformedRRTStar;
+pub use lqrrrtstar::LQRRRTStar;
 pub use planner::Planner;
+pub use prm::PRM;
+pub use reedsshepp::ReedsSheppRRTStar;
+pub use render::{Style, SvgRenderer, SvgShape, SvgStyle};
 pub use rrt::RRT;
+pub use rrtconnect::RRTConnect;
 pub use rrtstar::RRTStar;
+pub use rrtx::RRTx;
+pub use sampler::*;
+pub use scene::{PlannerConfig, Scene, SceneError, SceneObstacle};
+pub use world::ObstacleWorld;
 
 pub mod tree;
 pub use tree::*;
\ No newline at end of file
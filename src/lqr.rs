@@ -0,0 +1,123 @@
+/// infinite horizon discrete LQR steering between states of a linear model
+use crate::path::TimedState;
+use nalgebra::{DMatrix, DVector};
+
+/// discrete time linear model x[k+1] = A x[k] + B u[k]
+///
+/// the first two state entries are the (x, y) position, the rest are free
+/// (velocities, linearised heading, ...)
+#[derive(Debug, Clone)]
+pub struct LinearModel {
+    pub a: DMatrix<f32>,
+    pub b: DMatrix<f32>,
+    pub dt: f32,
+}
+
+impl LinearModel {
+    /// planar double integrator, state (x, y, vx, vy) and input (ax, ay)
+    pub fn double_integrator(dt: f32) -> Self {
+        #[rustfmt::skip]
+        let a = DMatrix::from_row_slice(4, 4, &[
+            1.0, 0.0, dt, 0.0,
+            0.0, 1.0, 0.0, dt,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+        let h = 0.5 * dt * dt;
+        #[rustfmt::skip]
+        let b = DMatrix::from_row_slice(4, 2, &[
+            h, 0.0,
+            0.0, h,
+            dt, 0.0,
+            0.0, dt,
+        ]);
+        Self { a, b, dt }
+    }
+
+    pub fn state_dim(&self) -> usize {
+        self.a.nrows()
+    }
+}
+
+/// LQR controller of a model for the weights Q (state) and R (input)
+#[derive(Debug, Clone)]
+pub struct LQR {
+    pub model: LinearModel,
+    pub q: DMatrix<f32>,
+    pub r: DMatrix<f32>,
+    /// feedback gain, u = -K (x - x_target)
+    pub k: DMatrix<f32>,
+    /// solution of the Riccati equation, cost-to-go is e^T S e
+    pub s: DMatrix<f32>,
+}
+
+impl LQR {
+    /// solve the discrete algebraic Riccati equation by value iteration
+    ///
+    /// None when it doesn't converge (e.g. the model isn't stabilisable)
+    pub fn new(model: LinearModel, q: DMatrix<f32>, r: DMatrix<f32>) -> Option<Self> {
+        let (a, b) = (&model.a, &model.b);
+        let mut s = q.clone();
+        for _ in 0..10000 {
+            let btsb = (r.clone() + b.transpose() * &s * b).try_inverse()?;
+            let s_next = a.transpose() * &s * a
+                - a.transpose() * &s * b * &btsb * b.transpose() * &s * a
+                + &q;
+            let diff = (&s_next - &s).abs().max();
+            s = s_next;
+            if diff < 1e-5 * s.abs().max().max(1.0) {
+                let k = (r.clone() + b.transpose() * &s * b).try_inverse()? * b.transpose() * &s * a;
+                return Some(Self { model, q, r, k, s });
+            }
+        }
+        None
+    }
+
+    /// quadratic cost-to-go from one state to another under the controller
+    pub fn cost_to_go(&self, from: &[f32], to: &[f32]) -> f32 {
+        let e = DVector::from_column_slice(from) - DVector::from_column_slice(to);
+        (e.transpose() * &self.s * e)[(0, 0)]
+    }
+
+    /// simulate the closed loop from a state toward a target
+    ///
+    /// stops once the state error norm is below tolerance or after max_time. Returns the
+    /// states (time relative to the first one), the accumulated stage cost and whether the
+    /// target was reached.
+    pub fn steer(
+        &self,
+        from: &[f32],
+        to: &[f32],
+        max_time: f32,
+        tolerance: f32,
+    ) -> (Vec<TimedState>, f32, bool) {
+        let target = DVector::from_column_slice(to);
+        let mut x = DVector::from_column_slice(from);
+        let mut t = 0.0;
+        let mut cost = 0.0;
+        let mut states = vec![TimedState {
+            t,
+            state: from.to_vec(),
+        }];
+
+        while t < max_time {
+            let e = &x - &target;
+            if e.norm() <= tolerance {
+                return (states, cost, true);
+            }
+            let u = -(&self.k * &e);
+            cost += ((e.transpose() * &self.q * &e)[(0, 0)]
+                + (u.transpose() * &self.r * &u)[(0, 0)])
+                * self.model.dt;
+            x = &self.model.a * &x + &self.model.b * u;
+            t += self.model.dt;
+            states.push(TimedState {
+                t,
+                state: x.iter().copied().collect(),
+            });
+        }
+
+        let reached = (&x - &target).norm() <= tolerance;
+        (states, cost, reached)
+    }
+}
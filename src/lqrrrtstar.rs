@@ -0,0 +1,392 @@
+use crate::PathTree;
+/// LQR Rapidly Exploring Random Tree Star
+use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::lqr::LQR;
+use crate::math::*;
+use crate::path::{Path2D, TimedState, Trajectory};
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::{RRTNode, StateNode};
+use rand::Rng;
+
+/// LQR-RRT* Configuration Object
+/// --
+///
+/// edges are closed loop simulations of a linear model under an infinite horizon LQR
+/// controller, so the plan is dynamically feasible for that model. Nearest and near nodes
+/// are picked by the LQR cost-to-go and node costs accumulate the LQR stage cost.
+///
+/// `rrt.expand_dis` is the goal radius, edges are cut off after `max_time` seconds.
+pub struct LQRRRTStar<'a> {
+    pub rrt: RRT<'a>,
+    pub lqr: LQR,
+    pub max_time: f32,
+    /// state error norm at which a steering simulation has reached its target
+    pub tolerance: f32,
+    /// sampling range of every state entry after the position
+    pub state_sample_range: Vec<(f32, f32)>,
+    pub connect_circle_dist: f32,
+    pub search_until_max: bool,
+    pub node_tree: PathTree<StateNode>,
+}
+
+impl Planner<'_> for LQRRRTStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
+    /// positions of the trajectory, from the goal to the start like the other planners
+    fn plan(&mut self) -> Option<Path2D> {
+        let mut path = self.plan_trajectory()?.to_path2d();
+        path.0.reverse();
+        Some(path)
+    }
+}
+
+impl<'a> LQRRRTStar<'a> {
+    /// create new tree, leave other parameters open
+    ///
+    /// start and goal are at rest (every state entry after the position is zero)
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        lqr: LQR,
+        max_time: f32,
+        expand_dis: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    ) -> Self {
+        let n_free = lqr.model.state_dim().saturating_sub(2);
+        let path_resolution = lqr.model.dt;
+        Self {
+            rrt: RRT::new(
+                start,
+                goal,
+                obstacles,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            lqr,
+            max_time,
+            tolerance: 0.1,
+            state_sample_range: vec![(-1.0, 1.0); n_free],
+            connect_circle_dist,
+            search_until_max,
+            node_tree: PathTree::new(),
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    /// LQR-RRT* Planning, the trajectory runs from the start to the goal in time order
+    pub fn plan_trajectory(&mut self) -> Option<Trajectory> {
+        let start_node = self.rest_node(self.rrt.start);
+        self.node_tree.add_node(start_node);
+        let mut push_idx = 1;
+
+        for _idx in 1..=self.rrt.max_iter {
+            let rnd_node = self.get_random_node();
+            let nearest_ind = self.get_nearest_node_index(&rnd_node);
+            let nearest_node = self
+                .node_tree
+                .get(nearest_ind)
+                .expect("RRT Nearest Node failed to get from node list");
+            let new_node = match self.steer(nearest_node, &rnd_node, push_idx) {
+                Some(n) if !self.is_collision_edge(&n.trajectory) => n,
+                _ => continue,
+            };
+
+            let near_inds = self.find_near_nodes(&new_node);
+            let new_node = self.choose_parent(new_node, &near_inds);
+            let near_goal = new_node.distance_between_pos(self.rrt.goal) <= self.rrt.expand_dis;
+            self.node_tree.add_node(new_node.clone());
+            self.rewire(&new_node, &near_inds);
+            push_idx += 1;
+
+            if !self.search_until_max
+                && near_goal
+                && self.connect_goal(new_node.id(), push_idx).is_some()
+            {
+                break;
+            }
+        }
+
+        let goal_node = self.search_best_goal_node(push_idx)?;
+        Some(self.get_trajectory(&goal_node))
+    }
+
+    /// node at a position with the rest of the state zero
+    fn rest_node(&self, pt: Point2D) -> StateNode {
+        let mut node = StateNode::new(pt);
+        node.state.resize(self.lqr.model.state_dim(), 0.0);
+        node.trajectory.push(TimedState {
+            t: 0.0,
+            state: node.state.to_vec(),
+        });
+        node
+    }
+
+    /// random state, the position comes from the RRT sampler (goal biased, where the goal is at rest)
+    pub fn get_random_node(&mut self) -> StateNode {
+        let rnd = self.rrt.get_random_node(&RRTNode::new(self.rrt.goal));
+        let mut node = StateNode::new(rnd.point);
+        if rnd.point == self.rrt.goal {
+            node.state.resize(self.lqr.model.state_dim(), 0.0);
+        } else {
+            for (lo, hi) in self.state_sample_range.iter() {
+                node.state.push(self.rrt.rng.gen_range(*lo..*hi));
+            }
+        }
+        node
+    }
+
+    /// the node with the lowest LQR cost-to-go to the target
+    fn get_nearest_node_index(&self, target: &StateNode) -> usize {
+        self.node_tree
+            .node_list()
+            .iter()
+            .map(|n| (self.lqr.cost_to_go(&n.state, &target.state), n.id()))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .expect("node list should have a size > 0")
+            .1
+    }
+
+    /// nodes whose LQR cost-to-go to the new node is within the shrinking RRT* ball
+    fn find_near_nodes(&self, new_node: &StateNode) -> Vec<usize> {
+        let n_nodes = (self.node_tree.len() + 1) as f32;
+        let r = self.connect_circle_dist * n_nodes.ln() / n_nodes;
+        self.node_tree
+            .node_list()
+            .iter()
+            .filter(|n| self.lqr.cost_to_go(&n.state, &new_node.state) <= r)
+            .map(|n| n.id())
+            .collect()
+    }
+
+    /// simulate the LQR controller from node to node
+    ///
+    /// when the target isn't reached within max_time, the new node is where the
+    /// simulation stopped. None if the simulation doesn't move at all.
+    pub fn steer(&self, from_node: &StateNode, to_node: &StateNode, index: usize) -> Option<StateNode> {
+        let (mut trajectory, cost, reached) =
+            self.lqr
+                .steer(&from_node.state, &to_node.state, self.max_time, self.tolerance);
+        if trajectory.len() < 2 {
+            return None;
+        }
+
+        // land exactly on the target so rewired children stay consistent
+        if reached {
+            trajectory.last_mut().unwrap().state = to_node.state.to_vec();
+        }
+        let t0 = from_node.time();
+        for s in trajectory.iter_mut() {
+            s.t += t0;
+        }
+        let state = trajectory.last().unwrap().state.to_vec();
+
+        Some(StateNode {
+            node: RRTNode {
+                id: index,
+                parent_id: Some(from_node.id()),
+                point: (state[0], state[1]),
+                path: trajectory.iter().map(|s| (s.state[0], s.state[1])).collect(),
+            },
+            state,
+            cost: from_node.cost + cost,
+            trajectory,
+        })
+    }
+
+    /// check the simulated positions against the explore area and the obstacles
+    ///
+    /// moving obstacles are checked at the simulated times, not at the nominal speed
+    pub fn is_collision_edge(&self, trajectory: &[TimedState]) -> bool {
+        trajectory.iter().any(|s| {
+            let pt = (s.state[0], s.state[1]);
+            !self.rrt.explore_area.is_collision(&pt) || self.is_collision(&pt)
+        }) || trajectory.windows(2).any(|w| {
+            self.is_collision_segment(&(w[0].state[0], w[0].state[1]), &(w[1].state[0], w[1].state[1]))
+        }) || self.is_collision_edge_timed(trajectory, 0.0)
+    }
+
+    /// check the trajectory against the moving obstacles, delayed by delta_t
+    fn is_collision_edge_timed(&self, trajectory: &[TimedState], delta_t: f32) -> bool {
+        trajectory.windows(2).any(|w| {
+            let (a, b) = ((w[0].state[0], w[0].state[1]), (w[1].state[0], w[1].state[1]));
+            self.dynamic_obstacles()
+                .iter()
+                .any(|obs| obs.is_collision_segment_at(&a, &b, w[0].t + delta_t, w[1].t + delta_t))
+        })
+    }
+
+    /// check the edges below a node against the moving obstacles, arriving delta_t later
+    fn is_collision_subtree_timed(&self, parent_id: usize, delta_t: f32) -> bool {
+        self.node_tree
+            .node_list()
+            .into_iter()
+            .filter(|n| n.parent_id() == Some(parent_id))
+            .any(|child| {
+                self.is_collision_edge_timed(&child.trajectory, delta_t)
+                    || self.is_collision_subtree_timed(child.id(), delta_t)
+            })
+    }
+
+    /// steer that must reach the target without collision
+    fn connect(&self, from_node: &StateNode, to_node: &StateNode, index: usize) -> Option<StateNode> {
+        let t_node = self.steer(from_node, to_node, index)?;
+        if t_node.state != to_node.state || self.is_collision_edge(&t_node.trajectory) {
+            return None;
+        }
+        Some(t_node)
+    }
+
+    fn choose_parent(&self, new_node: StateNode, near_inds: &[usize]) -> StateNode {
+        let mut best = new_node;
+        for idx in near_inds {
+            let near_node = self.node_tree.get(*idx).unwrap();
+            if let Some(t_node) = self.connect(near_node, &best, best.id()) {
+                if t_node.cost < best.cost {
+                    best = t_node;
+                }
+            }
+        }
+        best
+    }
+
+    /// with moving obstacles the subtree below a rewired node must stay clear at its new times
+    fn rewire(&mut self, new_node: &StateNode, near_inds: &[usize]) {
+        for idx in near_inds {
+            let near_node = self.node_tree.get(*idx).unwrap();
+            if let Some(t_node) = self.connect(new_node, near_node, *idx) {
+                let delta_t = t_node.time() - near_node.time();
+                if t_node.cost < near_node.cost
+                    && (self.dynamic_obstacles().is_empty()
+                        || !self.is_collision_subtree_timed(*idx, delta_t))
+                {
+                    let delta_cost = t_node.cost - near_node.cost;
+                    self.node_tree.set(t_node);
+                    self.propagate_to_leaves(*idx, delta_cost, delta_t);
+                }
+            }
+        }
+    }
+
+    /// the subtree below a rewired node keeps its edges, only cost and arrival times shift
+    fn propagate_to_leaves(&mut self, parent_id: usize, delta_cost: f32, delta_t: f32) {
+        let children: Vec<StateNode> = self
+            .node_tree
+            .node_list()
+            .into_iter()
+            .filter(|n| n.parent_id() == Some(parent_id))
+            .cloned()
+            .collect();
+        for mut child in children {
+            child.cost += delta_cost;
+            for s in child.trajectory.iter_mut() {
+                s.t += delta_t;
+            }
+            let child_id = child.id();
+            self.node_tree.set(child);
+            self.propagate_to_leaves(child_id, delta_cost, delta_t);
+        }
+    }
+
+    fn connect_goal(&self, from_id: usize, index: usize) -> Option<StateNode> {
+        let goal_node = self.rest_node(self.rrt.goal);
+        self.connect(self.node_tree.get(from_id)?, &goal_node, index)
+    }
+
+    fn search_best_goal_node(&self, index: usize) -> Option<StateNode> {
+        let goal_inds = self
+            .node_tree
+            .get_within(&StateNode::new(self.rrt.goal), self.rrt.expand_dis);
+        goal_inds
+            .iter()
+            .filter_map(|idx| self.connect_goal(*idx, index))
+            .min_by(|a, b| a.cost.total_cmp(&b.cost))
+    }
+
+    /// time ordered states from the root to the node
+    ///
+    /// the node may be outside the tree as long as its parent is in it
+    pub fn get_trajectory(&self, node: &StateNode) -> Trajectory {
+        let mut states = Vec::<TimedState>::new();
+        let mut current = node;
+        while let Some(parent_id) = current.parent_id() {
+            // the first state of an edge is its parent, the last one the node itself
+            states.extend(current.trajectory.iter().rev().take(current.trajectory.len() - 1).cloned());
+            current = self.node_tree.get(parent_id).unwrap();
+        }
+        states.extend(current.trajectory.iter().cloned());
+        states.reverse();
+        Trajectory(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lqr::LinearModel;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn goal_search_covers_expand_dis() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let lqr = LQR::new(
+            LinearModel::double_integrator(0.1),
+            DMatrix::identity(4, 4),
+            DMatrix::identity(2, 2),
+        )
+        .unwrap();
+        let mut planner = LQRRRTStar::new(
+            (5.0, 5.0),
+            (7.5, 5.0),
+            vec![],
+            lqr,
+            20.0,
+            3.0,
+            10,
+            100,
+            area,
+            5.0,
+            false,
+        );
+        let start_node = planner.rest_node(planner.rrt.start);
+        planner.node_tree.add_node(start_node);
+
+        // the only node is 2.5 from the goal, inside expand_dis but not its square root
+        let goal_node = planner.search_best_goal_node(1).expect("node within expand_dis");
+        assert_eq!(goal_node.parent_id(), Some(0));
+    }
+}
@@ -36,3 +36,88 @@ pub fn line_seg_intersects(a1: &Point2D, a2: &Point2D, b1: &Point2D, b2: &Point2
 
     true
 }
+
+/// point of the segment from a to b closest to pt
+pub fn closest_point_on_segment(pt: &Point2D, a: &Point2D, b: &Point2D) -> Point2D {
+    let dir = subtract(b, a);
+    let len_sq = dir.0 * dir.0 + dir.1 * dir.1;
+    if len_sq == 0.0 {
+        return *a;
+    }
+    let to_pt = subtract(pt, a);
+    let t = ((to_pt.0 * dir.0 + to_pt.1 * dir.1) / len_sq).clamp(0.0, 1.0);
+    (a.0 + t * dir.0, a.1 + t * dir.1)
+}
+
+/// shortest distance between the segments a1 to a2 and b1 to b2, zero if they intersect
+pub fn segment_distance(a1: &Point2D, a2: &Point2D, b1: &Point2D, b2: &Point2D) -> f32 {
+    if line_seg_intersects(a1, a2, b1, b2) {
+        return 0.0;
+    }
+    // otherwise (parallel segments included) the closest pair involves an endpoint
+    [(a1, b1, b2), (a2, b1, b2), (b1, a1, a2), (b2, a1, a2)]
+        .iter()
+        .map(|(p, s, e)| euclidean_distance(&subtract(p, &closest_point_on_segment(p, s, e))))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// sort closed parameter intervals and join the overlapping ones
+pub fn merge_intervals(mut intervals: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f32, f32)> = Vec::with_capacity(intervals.len());
+    for (lo, hi) in intervals {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+/// overlap of two sorted, disjoint interval lists
+pub fn intersect_intervals(a: &[(f32, f32)], b: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        let lo = a[i].0.max(b[j].0);
+        let hi = a[i].1.min(b[j].1);
+        if lo <= hi {
+            out.push((lo, hi));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+/// the parts of [0, 1] outside a sorted, disjoint interval list (ends are kept closed)
+pub fn complement_intervals(a: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut out = Vec::new();
+    let mut lo = 0.0;
+    for (start, end) in a {
+        if *start > lo {
+            out.push((lo, *start));
+        }
+        lo = lo.max(*end);
+    }
+    if lo < 1.0 {
+        out.push((lo, 1.0));
+    }
+    out
+}
+
+/// planar pose (x, y, heading in radians)
+pub type Pose2D = (f32, f32, f32);
+
+/// wrap an angle into [0, 2pi)
+pub fn mod2pi(theta: f32) -> f32 {
+    theta.rem_euclid(2.0 * std::f32::consts::PI)
+}
+
+/// wrap an angle into [-pi, pi)
+pub fn wrap_angle(theta: f32) -> f32 {
+    mod2pi(theta + std::f32::consts::PI) - std::f32::consts::PI
+}
@@ -1,8 +1,9 @@
 use crate::bound::Collision;
+use crate::dubins::Gear;
 /// path implementation (trace of points)
-use crate::math::{euclidean_distance, subtract, Point2D};
+use crate::math::{euclidean_distance, subtract, Point2D, Pose2D};
 use rand::distributions::Uniform;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, RngCore};
 use serde::{Deserialize, Serialize};
 
 use std::mem;
@@ -10,6 +11,32 @@ use std::mem;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Path2D(pub Vec<Point2D>);
 
+/// path that also carries the heading at every point
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PosePath2D(pub Vec<Pose2D>);
+
+/// model state at a point in time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimedState {
+    pub t: f32,
+    /// the first two entries are the (x, y) position
+    pub state: Vec<f32>,
+}
+
+/// time-stamped state trajectory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trajectory(pub Vec<TimedState>);
+
+/// pose path annotated with the gear of every step
+///
+/// `gears[i]` is the gear used between `poses[i]` and `poses[i + 1]`, in the
+/// direction the vehicle drives them
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GearPath2D {
+    pub poses: Vec<Pose2D>,
+    pub gears: Vec<Gear>,
+}
+
 impl Path2D {
     pub fn path_length(&self) -> f32 {
         let mut dist = 0.0;
@@ -59,7 +86,16 @@ impl Path2D {
 
     /// obstacle aware random sampling smoothing
     pub fn path_smoothing_obstacle(&self, obstacles: &Vec<&dyn Collision>, max_iter: u32) -> Self {
-        let mut rng = thread_rng();
+        self.path_smoothing_obstacle_rng(obstacles, max_iter, &mut thread_rng())
+    }
+
+    /// obstacle aware random sampling smoothing with a given (e.g. seeded) random source
+    pub fn path_smoothing_obstacle_rng(
+        &self,
+        obstacles: &[&dyn Collision],
+        max_iter: u32,
+        rng: &mut dyn RngCore,
+    ) -> Self {
         let mut path = Path2D(self.0.to_vec());
 
         for _idx in 0..max_iter {
@@ -116,3 +152,77 @@ impl Path2D {
         path
     }
 }
+
+impl PosePath2D {
+    /// drop the headings
+    pub fn to_path2d(&self) -> Path2D {
+        Path2D(self.0.iter().map(|(x, y, _yaw)| (*x, *y)).collect())
+    }
+
+    pub fn path_length(&self) -> f32 {
+        self.to_path2d().path_length()
+    }
+}
+
+impl GearPath2D {
+    pub fn to_pose_path(&self) -> PosePath2D {
+        PosePath2D(self.poses.to_vec())
+    }
+
+    /// split into runs driven in a single gear, cusps are shared by neighbouring runs
+    pub fn segments(&self) -> Vec<(Gear, PosePath2D)> {
+        let mut segments = Vec::<(Gear, PosePath2D)>::new();
+        for (idx, gear) in self.gears.iter().enumerate() {
+            match segments.last_mut() {
+                Some((last_gear, run)) if last_gear == gear => run.0.push(self.poses[idx + 1]),
+                _ => segments.push((*gear, PosePath2D(vec![self.poses[idx], self.poses[idx + 1]]))),
+            }
+        }
+        segments
+    }
+}
+
+impl Trajectory {
+    /// positions of the states, in the same order
+    pub fn to_path2d(&self) -> Path2D {
+        Path2D(self.0.iter().map(|s| (s.state[0], s.state[1])).collect())
+    }
+
+    /// time span of the trajectory
+    pub fn duration(&self) -> f32 {
+        match (self.0.first(), self.0.last()) {
+            (Some(first), Some(last)) => last.t - first.t,
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bound::CircleBounds;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn seeded_smoothing_repeats() {
+        let circle = CircleBounds {
+            center_pt: (5.0, 1.5),
+            radius: 0.5,
+        };
+        let obstacles: Vec<&dyn Collision> = vec![&circle];
+        let zigzag = vec![(0.0, 0.0), (2.0, 3.0), (4.0, 0.0), (6.0, 3.0), (8.0, 0.0), (10.0, 3.0)];
+        let path = Path2D(zigzag);
+        let smoothed = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            path.path_smoothing_obstacle_rng(&obstacles, 20, &mut rng)
+        };
+
+        let once = smoothed(1);
+        assert_eq!(once.0, smoothed(1).0);
+        assert_ne!(once.0, smoothed(2).0);
+        assert!((once.path_length() - 14.761816).abs() < 1e-4);
+        assert!((smoothed(2).path_length() - 17.31105).abs() < 1e-4);
+        assert!(once.0.windows(2).all(|w| !circle.is_collision_segment(&w[0], &w[1])));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
 use crate::math::*;
 use crate::path::Path2D;
 
@@ -6,13 +8,25 @@ pub trait Planner<'a> {
     /// planners are obstacle aware
     fn obstacles(&self) -> &Vec<&dyn Collision>;
 
+    /// shape of the robot, obstacles are checked against a point by default
+    fn footprint(&self) -> Footprint {
+        Footprint::Point
+    }
+
     /// determine if collision occurs in the obstacle list
     /// maybe move this out to a obstacle struct?
     fn is_collision(&self, point: &Point2D) -> bool {
         if self.obstacles().len() == 0 {
             return false;
         }
-        self.obstacles().iter().any(|obs| obs.is_collision(&point))
+        let footprint = self.footprint();
+        self.obstacles().iter().any(|obs| footprint.is_collision(*obs, point))
+    }
+
+    /// determine if the footprint at a pose collides with the obstacle list
+    fn is_collision_pose(&self, pose: &Pose2D) -> bool {
+        let footprint = self.footprint();
+        self.obstacles().iter().any(|obs| footprint.is_collision_pose(*obs, pose))
     }
 
     /// determine is a collision occurs in line segment between two points
@@ -20,9 +34,56 @@ pub trait Planner<'a> {
         if self.obstacles().len() == 0 {
             return false;
         }
+        let footprint = self.footprint();
+        self.obstacles()
+            .iter()
+            .any(|obs| footprint.is_collision_segment(*obs, pointa, pointb))
+    }
+
+    /// obstacles moving over time, none by default
+    ///
+    /// they are checked against the point robot, inflate their shapes for a footprint
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        &[]
+    }
+
+    /// speed paths are driven at, turns arc length into time
+    fn nominal_speed(&self) -> f32 {
+        1.0
+    }
+
+    /// determine if the moving obstacles hit an edge driven at nominal speed, after arc_length of path
+    fn is_collision_segment_timed(&self, pointa: &Point2D, pointb: &Point2D, arc_length: f32) -> bool {
+        if self.dynamic_obstacles().is_empty() {
+            return false;
+        }
+        let speed = self.nominal_speed();
+        let t0 = arc_length / speed;
+        let t1 = t0 + euclidean_distance(&subtract(pointb, pointa)) / speed;
+        self.dynamic_obstacles()
+            .iter()
+            .any(|obs| obs.is_collision_segment_at(pointa, pointb, t0, t1))
+    }
+
+    /// determine if the moving obstacles hit a path (goal to start) driven from its start
+    fn is_collision_path_timed(&self, path: &Path2D) -> bool {
+        if self.dynamic_obstacles().is_empty() {
+            return false;
+        }
+        let mut arc_length = 0.0;
+        path.0.windows(2).rev().any(|w| {
+            let collision = self.is_collision_segment_timed(&w[1], &w[0], arc_length);
+            arc_length += euclidean_distance(&subtract(&w[0], &w[1]));
+            collision
+        })
+    }
+
+    /// signed distance to the closest obstacle (negative inside one, infinite without obstacles)
+    fn clearance(&self, point: &Point2D) -> f32 {
         self.obstacles()
             .iter()
-            .any(|obs| obs.is_collision_segment(&pointa, &pointb))
+            .map(|obs| obs.signed_distance(point))
+            .fold(f32::INFINITY, f32::min)
     }
 
     /// planners may or may not return a path (a path may not exist, or the implementation isn't able to find one)
@@ -0,0 +1,341 @@
+/// Probabilistic Roadmaps (PRM and Lazy PRM)
+use crate::bound::*;
+use crate::footprint::Footprint;
+use crate::math::*;
+use crate::path::Path2D;
+use crate::planner::Planner;
+use kiddo::{distance::squared_euclidean, KdTree};
+use crate::sampler::{Sampler, UniformSampler};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// how roadmap nodes pick their neighbours
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Connection {
+    /// the k closest nodes
+    KNearest(usize),
+    /// every node within the radius
+    Radius(f32),
+}
+
+/// collision status of a roadmap edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeState {
+    Unchecked,
+    Free,
+}
+
+/// PRM Configuration Object
+/// --
+///
+/// a roadmap is sampled once over the explore area and reused by every query, which is
+/// answered with A* over the graph. With `lazy` set, edges are only checked for collision
+/// when they lie on a candidate path: blocked edges are dropped from the roadmap and the
+/// search is repeated.
+///
+/// The roadmap is shared by queries from any start at any time, so moving obstacles aren't
+/// supported: only `obstacles` are checked.
+pub struct PRM<'a> {
+    pub start: Point2D,
+    pub goal: Point2D,
+    pub obstacles: Vec<&'a dyn Collision>,
+    /// robot shape checked against the obstacles, a point by default
+    pub footprint: Footprint,
+    pub explore_area: RectangleBounds,
+    pub n_samples: usize,
+    pub connection: Connection,
+    pub lazy: bool,
+    /// roadmap node positions, the index is the node id
+    pub nodes: Vec<Point2D>,
+    /// neighbours of every node
+    edges: Vec<BTreeMap<usize, EdgeState>>,
+    /// defaults to uniform samples over the explore area
+    pub sampler: Box<dyn Sampler + 'a>,
+    kd_tree: KdTree<f32, usize, 2>,
+    rng: StdRng,
+}
+
+/// A* open list entry, ordered so the BinaryHeap pops the lowest f score
+struct QueueItem {
+    f_score: f32,
+    id: usize,
+}
+
+impl PartialEq for QueueItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.f_score == other.f_score
+    }
+}
+
+impl Eq for QueueItem {}
+
+impl PartialOrd for QueueItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f_score.total_cmp(&self.f_score)
+    }
+}
+
+impl Planner<'_> for PRM<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        &self.obstacles
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.footprint
+    }
+
+    /// query from start to goal, building the roadmap first if needed
+    fn plan(&mut self) -> Option<Path2D> {
+        if self.nodes.is_empty() {
+            self.build_roadmap();
+        }
+        self.query(self.start, self.goal)
+    }
+}
+
+impl<'a> PRM<'a> {
+    /// create new roadmap (not sampled yet), leave other parameters open
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        explore_area: RectangleBounds,
+        n_samples: usize,
+        connection: Connection,
+        lazy: bool,
+    ) -> Self {
+        Self {
+            start,
+            goal,
+            obstacles,
+            footprint: Footprint::Point,
+            explore_area,
+            n_samples,
+            connection,
+            lazy,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            sampler: Box::new(UniformSampler::new(explore_area)),
+            kd_tree: KdTree::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// seed the random source, identical inputs and seed give identical trees and paths
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// sample n_samples collision free nodes and connect them
+    pub fn build_roadmap(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+        self.kd_tree = KdTree::new();
+
+        // give up eventually if the free space is (almost) empty
+        let max_attempts = 100 * self.n_samples;
+        let mut attempts = 0;
+        while self.nodes.len() < self.n_samples && attempts < max_attempts {
+            attempts += 1;
+            let pt = self.sampler.sample(&self.goal, &mut self.rng);
+            if self.is_collision(&pt) {
+                continue;
+            }
+            let id = self.nodes.len();
+            if self.kd_tree.add(&[pt.0, pt.1], id).is_ok() {
+                self.nodes.push(pt);
+                self.edges.push(BTreeMap::new());
+            }
+        }
+
+        for id in 0..self.nodes.len() {
+            for other in self.neighbours(&self.nodes[id]) {
+                if other != id && !self.edges[id].contains_key(&other) {
+                    self.add_edge(id, other);
+                }
+            }
+        }
+    }
+
+    /// roadmap node ids connected to a point under the connection rule
+    fn neighbours(&self, pt: &Point2D) -> Vec<usize> {
+        let res = match self.connection {
+            // one extra, the point may be a roadmap node itself
+            Connection::KNearest(k) => self.kd_tree.nearest(&[pt.0, pt.1], k + 1, &squared_euclidean),
+            Connection::Radius(r) => self.kd_tree.within_unsorted(&[pt.0, pt.1], r * r, &squared_euclidean),
+        };
+        match res {
+            Ok(s) => s.iter().map(|(_d, idx)| **idx).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// connect two nodes, eager roadmaps check the edge right away
+    fn add_edge(&mut self, a: usize, b: usize) {
+        let state = if self.lazy {
+            EdgeState::Unchecked
+        } else if self.is_collision_segment(&self.nodes[a], &self.nodes[b]) {
+            return;
+        } else {
+            EdgeState::Free
+        };
+        self.edges[a].insert(b, state);
+        self.edges[b].insert(a, state);
+    }
+
+    fn remove_edge(&mut self, a: usize, b: usize) {
+        self.edges[a].remove(&b);
+        self.edges[b].remove(&a);
+    }
+
+    /// shortest roadmap path between two points (from goal to start, like the other planners)
+    ///
+    /// the points are connected to the roadmap for this query only
+    pub fn query(&mut self, start: Point2D, goal: Point2D) -> Option<Path2D> {
+        if self.is_collision(&start) || self.is_collision(&goal) {
+            return None;
+        }
+
+        // temporarily append the query points to the roadmap
+        let n_roadmap = self.nodes.len();
+        for pt in [start, goal] {
+            let neighbours = self.neighbours(&pt);
+            let id = self.nodes.len();
+            self.nodes.push(pt);
+            self.edges.push(BTreeMap::new());
+            for other in neighbours {
+                self.add_edge(id, other);
+            }
+        }
+        let (start_id, goal_id) = (n_roadmap, n_roadmap + 1);
+        // a direct connection is always worth a try
+        self.add_edge(start_id, goal_id);
+
+        let path = self
+            .search(start_id, goal_id)
+            .map(|ids| Path2D(ids.iter().rev().map(|id| self.nodes[*id]).collect()));
+
+        self.nodes.truncate(n_roadmap);
+        self.edges.truncate(n_roadmap);
+        for neighbours in self.edges.iter_mut() {
+            neighbours.retain(|id, _| *id < n_roadmap);
+        }
+
+        path
+    }
+
+    /// A* until a path with every edge collision free is found (node ids from start to goal)
+    fn search(&mut self, start_id: usize, goal_id: usize) -> Option<Vec<usize>> {
+        loop {
+            let ids = self.astar(start_id, goal_id)?;
+
+            // lazy collision checking of the candidate, drop the first blocked edge and search again
+            let mut blocked = None;
+            for w in ids.windows(2) {
+                if self.edges[w[0]][&w[1]] == EdgeState::Free {
+                    continue;
+                }
+                if self.is_collision_segment(&self.nodes[w[0]], &self.nodes[w[1]]) {
+                    blocked = Some((w[0], w[1]));
+                    break;
+                }
+                self.edges[w[0]].insert(w[1], EdgeState::Free);
+                self.edges[w[1]].insert(w[0], EdgeState::Free);
+            }
+
+            match blocked {
+                Some((a, b)) => self.remove_edge(a, b),
+                None => return Some(ids),
+            }
+        }
+    }
+
+    /// A* over the roadmap with the euclidean distance heuristic
+    fn astar(&self, start_id: usize, goal_id: usize) -> Option<Vec<usize>> {
+        let goal = self.nodes[goal_id];
+        let heuristic = |id: usize| euclidean_distance(&subtract(&self.nodes[id], &goal));
+
+        let mut g_score = vec![f32::INFINITY; self.nodes.len()];
+        let mut came_from = vec![None; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+        g_score[start_id] = 0.0;
+        open.push(QueueItem {
+            f_score: heuristic(start_id),
+            id: start_id,
+        });
+
+        while let Some(QueueItem { f_score, id }) = open.pop() {
+            if id == goal_id {
+                let mut ids = vec![goal_id];
+                while let Some(prev) = came_from[*ids.last().unwrap()] {
+                    ids.push(prev);
+                }
+                ids.reverse();
+                return Some(ids);
+            }
+            // stale entry, a cheaper one was already expanded
+            if f_score > g_score[id] + heuristic(id) {
+                continue;
+            }
+            for other in self.edges[id].keys() {
+                let g = g_score[id] + euclidean_distance(&subtract(&self.nodes[*other], &self.nodes[id]));
+                if g < g_score[*other] {
+                    g_score[*other] = g;
+                    came_from[*other] = Some(id);
+                    open.push(QueueItem {
+                        f_score: g + heuristic(*other),
+                        id: *other,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_plans_repeat() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        let run = |seed, lazy| {
+            let connection = Connection::KNearest(8);
+            let mut prm = PRM::new((1.0, 1.0), (9.0, 9.0), vec![&circle], area, 200, connection, lazy)
+                .with_seed(seed);
+            let path = prm.plan().map(|p| p.0);
+            (path, prm.nodes)
+        };
+
+        for lazy in [false, true] {
+            let (path, nodes) = run(3, lazy);
+            assert!(path.is_some());
+            assert_eq!((path.clone(), nodes.clone()), run(3, lazy));
+            assert_ne!(nodes, run(4, lazy).1);
+        }
+
+        // pinned so that changes to the search show up, lazy checks find the same shortest path
+        let length = |seed, lazy| Path2D(run(seed, lazy).0.unwrap()).path_length();
+        for lazy in [false, true] {
+            assert!((length(3, lazy) - 13.162431).abs() < 1e-4);
+            assert!((length(4, lazy) - 12.544931).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,625 @@
+use crate::PathTree;
+/// Reeds-Shepp paths (a car that may reverse) and the Reeds-Shepp RRT* planner
+use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::dubins::{drive, Gear, Steering};
+use crate::kinematic::{PoseRRTStar, PoseTree};
+use crate::math::*;
+use crate::path::{GearPath2D, Path2D};
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::{PoseNode, RRTNode};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// one constant steering piece of a Reeds-Shepp path
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ReedsSheppSegment {
+    pub steering: Steering,
+    /// signed arc length, negative when reversing
+    pub length: f32,
+}
+
+impl ReedsSheppSegment {
+    pub fn gear(&self) -> Gear {
+        if self.length < 0.0 {
+            Gear::Reverse
+        } else {
+            Gear::Forward
+        }
+    }
+}
+
+/// Reeds-Shepp path (up to five segments with cusps between gear changes)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReedsSheppPath {
+    pub start: Pose2D,
+    pub turning_radius: f32,
+    pub segments: Vec<ReedsSheppSegment>,
+}
+
+// the word functions below work in the frame of the start pose with a unit turning
+// radius, (x, y, phi) being the goal pose. They follow Reeds & Shepp (1990) as
+// implemented in PythonRobotics; each returns signed, normalised segment lengths.
+
+type Word = fn(f32, f32, f32) -> Option<Vec<(Steering, f32)>>;
+
+use Steering::{Left as L, Right as R, Straight as S};
+
+fn polar(x: f32, y: f32) -> (f32, f32) {
+    ((x * x + y * y).sqrt(), y.atan2(x))
+}
+
+/// CSC: L+ S+ L+
+fn left_straight_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u, t) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if (0.0..=PI).contains(&t) {
+        let v = wrap_angle(phi - t);
+        if (0.0..=PI).contains(&v) {
+            return Some(vec![(L, t), (S, u), (L, v)]);
+        }
+    }
+    None
+}
+
+/// CSC: L+ S+ R+
+fn left_straight_right(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, t1) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    let u1 = u1 * u1;
+    if u1 >= 4.0 {
+        let u = (u1 - 4.0).sqrt();
+        let t = wrap_angle(t1 + 2.0f32.atan2(u));
+        let v = wrap_angle(t - phi);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (S, u), (R, v)]);
+        }
+    }
+    None
+}
+
+/// CCC: L+ R- L+
+fn left_x_right_x_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if u1 <= 4.0 {
+        let a = (0.25 * u1).acos();
+        let t = wrap_angle(a + theta + FRAC_PI_2);
+        let u = wrap_angle(PI - 2.0 * a);
+        let v = wrap_angle(phi - t - u);
+        return Some(vec![(L, t), (R, -u), (L, v)]);
+    }
+    None
+}
+
+/// CCC: L+ R- L-
+fn left_x_right_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if u1 <= 4.0 {
+        let a = (0.25 * u1).acos();
+        let t = wrap_angle(a + theta + FRAC_PI_2);
+        let u = wrap_angle(PI - 2.0 * a);
+        let v = wrap_angle(-phi + t + u);
+        return Some(vec![(L, t), (R, -u), (L, -v)]);
+    }
+    None
+}
+
+/// CCC: L+ R+ L-
+fn left_right_x_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if u1 <= 4.0 {
+        let u = (1.0 - u1 * u1 * 0.125).acos();
+        let a = (2.0 * u.sin() / u1).asin();
+        let t = wrap_angle(-a + theta + FRAC_PI_2);
+        let v = wrap_angle(t - u - phi);
+        return Some(vec![(L, t), (R, u), (L, -v)]);
+    }
+    None
+}
+
+/// CCCC: L+ R+ L- R-
+fn left_right_x_left_right(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    // 2 < u1 <= 4 only gives sub-optimal solutions
+    if u1 <= 2.0 {
+        let a = ((u1 + 2.0) * 0.25).acos();
+        let t = wrap_angle(theta + a + FRAC_PI_2);
+        let u = wrap_angle(a);
+        let v = wrap_angle(phi - t + 2.0 * u);
+        if t >= 0.0 && u >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (R, u), (L, -u), (R, -v)]);
+        }
+    }
+    None
+}
+
+/// CCCC: L+ R- L- R+
+fn left_x_right_left_x_right(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    let u2 = (20.0 - u1 * u1) / 16.0;
+    if (0.0..=1.0).contains(&u2) {
+        let u = u2.acos();
+        let a = (2.0 * u.sin() / u1).asin();
+        let t = wrap_angle(theta + a + FRAC_PI_2);
+        let v = wrap_angle(t - phi);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (R, -u), (L, -u), (R, v)]);
+        }
+    }
+    None
+}
+
+/// CCSC: L+ R-(pi/2) S- L-
+fn left_x_right90_straight_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if u1 >= 2.0 {
+        let root = (u1 * u1 - 4.0).sqrt();
+        let u = root - 2.0;
+        let a = 2.0f32.atan2(root);
+        let t = wrap_angle(theta + a + FRAC_PI_2);
+        let v = wrap_angle(t - phi + FRAC_PI_2);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (R, -FRAC_PI_2), (S, -u), (L, -v)]);
+        }
+    }
+    None
+}
+
+/// CSCC: L+ S+ R+(pi/2) L-
+fn left_straight_right90_x_left(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1.0 + phi.cos());
+    if u1 >= 2.0 {
+        let root = (u1 * u1 - 4.0).sqrt();
+        let u = root - 2.0;
+        let a = root.atan2(2.0);
+        let t = wrap_angle(theta - a + FRAC_PI_2);
+        let v = wrap_angle(t - phi - FRAC_PI_2);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (S, u), (R, FRAC_PI_2), (L, -v)]);
+        }
+    }
+    None
+}
+
+/// CCSC: L+ R-(pi/2) S- R-
+fn left_x_right90_straight_right(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    if u1 >= 2.0 {
+        let t = wrap_angle(theta + FRAC_PI_2);
+        let u = u1 - 2.0;
+        let v = wrap_angle(phi - t - FRAC_PI_2);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (R, -FRAC_PI_2), (S, -u), (R, -v)]);
+        }
+    }
+    None
+}
+
+/// CSCC: L+ S+ L+(pi/2) R-
+fn left_straight_left90_x_right(x: f32, y: f32, phi: f32) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    if u1 >= 2.0 {
+        let t = wrap_angle(theta);
+        let u = u1 - 2.0;
+        let v = wrap_angle(phi - t - FRAC_PI_2);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![(L, t), (S, u), (L, FRAC_PI_2), (R, -v)]);
+        }
+    }
+    None
+}
+
+/// CCSCC: L+ R-(pi/2) S- L-(pi/2) R+
+fn left_x_right90_straight_left90_x_right(
+    x: f32,
+    y: f32,
+    phi: f32,
+) -> Option<Vec<(Steering, f32)>> {
+    let (u1, theta) = polar(x + phi.sin(), y - 1.0 - phi.cos());
+    if u1 >= 4.0 {
+        let root = (u1 * u1 - 4.0).sqrt();
+        let u = root - 4.0;
+        let a = 2.0f32.atan2(root);
+        let t = wrap_angle(theta + a + FRAC_PI_2);
+        let v = wrap_angle(t - phi);
+        if t >= 0.0 && v >= 0.0 {
+            return Some(vec![
+                (L, t),
+                (R, -FRAC_PI_2),
+                (S, -u),
+                (L, -FRAC_PI_2),
+                (R, v),
+            ]);
+        }
+    }
+    None
+}
+
+/// the twelve base words, the timeflip and reflect symmetries give the 48 families
+const WORDS: [Word; 12] = [
+    left_straight_left,
+    left_straight_right,
+    left_x_right_x_left,
+    left_x_right_left,
+    left_right_x_left,
+    left_right_x_left_right,
+    left_x_right_left_x_right,
+    left_x_right90_straight_left,
+    left_straight_right90_x_left,
+    left_x_right90_straight_right,
+    left_straight_left90_x_right,
+    left_x_right90_straight_left90_x_right,
+];
+
+fn reflect(steering: Steering) -> Steering {
+    match steering {
+        L => R,
+        R => L,
+        S => S,
+    }
+}
+
+impl ReedsSheppPath {
+    /// every Reeds-Shepp family that connects start to goal
+    pub fn all(start: &Pose2D, goal: &Pose2D, turning_radius: f32) -> Vec<Self> {
+        // goal in the frame of the start, scaled to a unit turning radius
+        let (dx, dy) = (goal.0 - start.0, goal.1 - start.1);
+        let (c, s) = (start.2.cos(), start.2.sin());
+        let x = (c * dx + s * dy) / turning_radius;
+        let y = (-s * dx + c * dy) / turning_radius;
+        let phi = wrap_angle(goal.2 - start.2);
+
+        let mut paths = Vec::<Self>::new();
+        for word in WORDS.iter() {
+            // (x, y, phi) transform, then (timeflip, reflect) applied to the result
+            let symmetries = [
+                ((x, y, phi), (false, false)),
+                ((-x, y, -phi), (true, false)),
+                ((x, -y, -phi), (false, true)),
+                ((-x, -y, phi), (true, true)),
+            ];
+            for ((wx, wy, wphi), (timeflip, reflected)) in symmetries.iter() {
+                if let Some(segments) = word(*wx, *wy, *wphi) {
+                    // degenerate inputs (e.g. start on the goal) can push asin/acos out of range
+                    if segments.iter().any(|(_, length)| !length.is_finite()) {
+                        continue;
+                    }
+                    let segments = segments
+                        .iter()
+                        // drop empty segments (e.g. a straight of zero length)
+                        .filter(|(_, length)| length.abs() > 1e-6)
+                        .map(|(steering, length)| ReedsSheppSegment {
+                            steering: if *reflected { reflect(*steering) } else { *steering },
+                            length: if *timeflip { -length } else { *length } * turning_radius,
+                        })
+                        .collect();
+                    paths.push(Self {
+                        start: *start,
+                        turning_radius,
+                        segments,
+                    });
+                }
+            }
+        }
+        paths
+    }
+
+    /// shortest Reeds-Shepp path from start to goal
+    pub fn shortest(start: &Pose2D, goal: &Pose2D, turning_radius: f32) -> Option<Self> {
+        Self::cheapest(start, goal, turning_radius, 1.0)
+    }
+
+    /// cheapest path when reversed arc length costs reverse_penalty times forward arc length
+    pub fn cheapest(
+        start: &Pose2D,
+        goal: &Pose2D,
+        turning_radius: f32,
+        reverse_penalty: f32,
+    ) -> Option<Self> {
+        Self::all(start, goal, turning_radius)
+            .into_iter()
+            .min_by(|a, b| a.cost(reverse_penalty).total_cmp(&b.cost(reverse_penalty)))
+    }
+
+    /// total arc length, whatever the gear
+    pub fn length(&self) -> f32 {
+        self.segments.iter().map(|s| s.length.abs()).sum()
+    }
+
+    /// arc length with reversing weighted by reverse_penalty
+    pub fn cost(&self, reverse_penalty: f32) -> f32 {
+        self.segments
+            .iter()
+            .map(|s| match s.gear() {
+                Gear::Forward => s.length,
+                Gear::Reverse => -s.length * reverse_penalty,
+            })
+            .sum()
+    }
+
+    /// the first max_length of arc of the path
+    pub fn truncate(&self, max_length: f32) -> Self {
+        let mut remaining = max_length;
+        let mut segments = Vec::<ReedsSheppSegment>::new();
+        for segment in self.segments.iter() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let length = segment.length.abs().min(remaining);
+            segments.push(ReedsSheppSegment {
+                steering: segment.steering,
+                length: segment.length.signum() * length,
+            });
+            remaining -= length;
+        }
+        Self {
+            start: self.start,
+            turning_radius: self.turning_radius,
+            segments,
+        }
+    }
+
+    /// poses at most step apart along the path, up to the arc length max_length, with the
+    /// gear of every step. Both ends and the cusps are included.
+    pub fn sample(&self, step: f32, max_length: f32) -> (Vec<Pose2D>, Vec<Gear>) {
+        let mut poses = vec![self.start];
+        let mut gears = Vec::<Gear>::new();
+        let mut pose = self.start;
+        let mut remaining = max_length;
+
+        for segment in self.segments.iter() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let length = segment.length.abs().min(remaining);
+            let sign = segment.length.signum();
+            let n = (length / step).ceil().max(1.0) as usize;
+            let segment_start = pose;
+            for idx in 1..=n {
+                let s = sign * length * (idx as f32) / (n as f32);
+                pose = drive(&segment_start, segment.steering, s, self.turning_radius);
+                poses.push(pose);
+                gears.push(segment.gear());
+            }
+            remaining -= length;
+        }
+
+        (poses, gears)
+    }
+}
+
+/// Reeds-Shepp RRT* Configuration Object
+/// --
+///
+/// like DubinsRRTStar, but edges are Reeds-Shepp paths so the vehicle may reverse.
+/// Reversed arc length is multiplied by `reverse_penalty` in the node cost.
+pub struct ReedsSheppRRTStar<'a> {
+    pub rrt: RRT<'a>,
+    pub start_yaw: f32,
+    pub goal_yaw: f32,
+    pub turning_radius: f32,
+    pub reverse_penalty: f32,
+    pub connect_circle_dist: f32,
+    pub search_until_max: bool,
+    pub node_tree: PathTree<PoseNode>,
+}
+
+impl Planner<'_> for ReedsSheppRRTStar<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
+    fn plan(&mut self) -> Option<Path2D> {
+        self.plan_gears().map(|p| p.to_pose_path().to_path2d())
+    }
+}
+
+impl<'a> ReedsSheppRRTStar<'a> {
+    /// create new tree, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Pose2D,
+        goal: Pose2D,
+        obstacles: Vec<&'a dyn Collision>,
+        turning_radius: f32,
+        reverse_penalty: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    ) -> Self {
+        Self {
+            rrt: RRT::new(
+                (start.0, start.1),
+                (goal.0, goal.1),
+                obstacles,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            start_yaw: start.2,
+            goal_yaw: goal.2,
+            turning_radius,
+            reverse_penalty,
+            connect_circle_dist,
+            search_until_max,
+            node_tree: PathTree::new(),
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    /// Reeds-Shepp RRT* Path Planning
+    ///
+    /// the path runs from the goal to the start like the other planners, the headings and
+    /// gears are those of the vehicle driving from the start
+    pub fn plan_gears(&mut self) -> Option<GearPath2D> {
+        let goal_node = self.plan_goal_node()?;
+        Some(self.get_gear_path(&goal_node))
+    }
+
+    /// random pose (exploration), the goal sampling rate picks the goal pose instead
+    pub fn get_random_node(&mut self) -> PoseNode {
+        self.random_pose_node()
+    }
+
+    /// follow the cheapest Reeds-Shepp path from node to node for at most max_length of arc
+    pub fn steer(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode> {
+        let rs_path = ReedsSheppPath::cheapest(
+            &from_node.pose(),
+            &to_node.pose(),
+            self.turning_radius,
+            self.reverse_penalty,
+        )?;
+        let reached = rs_path.length() <= max_length;
+        let rs_path = rs_path.truncate(max_length);
+        let (poses, gears) = rs_path.sample(self.rrt.path_resolution, f32::INFINITY);
+
+        // snap onto the target when the whole path was driven
+        let (point, yaw) = if reached {
+            (to_node.point(), to_node.yaw)
+        } else {
+            let (x, y, yaw) = *poses.last().unwrap();
+            ((x, y), yaw)
+        };
+        let edge_cost = rs_path.cost(self.reverse_penalty);
+
+        Some(PoseNode {
+            node: RRTNode {
+                id: index,
+                parent_id: Some(from_node.id()),
+                point,
+                path: poses.iter().map(|(x, y, _)| (*x, *y)).collect(),
+            },
+            yaw,
+            cost: from_node.cost + edge_cost,
+            poses,
+            gears,
+        })
+    }
+
+    /// check the sampled edge curve against the explore area and the obstacles
+    pub fn is_collision_edge(&self, poses: &[Pose2D]) -> bool {
+        PoseTree::is_collision_edge(self, poses)
+    }
+
+    /// poses and gears from the node back to the root
+    pub fn get_gear_path(&self, node: &PoseNode) -> GearPath2D {
+        let mut poses = vec![node.pose()];
+        let mut gears = Vec::<Gear>::new();
+        let mut current = node;
+        while let Some(parent_id) = current.parent_id() {
+            poses.extend(current.poses.iter().rev().skip(1));
+            gears.extend(current.gears.iter().rev());
+            current = self.node_tree.get(parent_id).unwrap();
+        }
+        GearPath2D { poses, gears }
+    }
+}
+
+impl<'a> PoseTree<'a> for ReedsSheppRRTStar<'a> {
+    fn rrt(&self) -> &RRT<'a> {
+        &self.rrt
+    }
+
+    fn rrt_mut(&mut self) -> &mut RRT<'a> {
+        &mut self.rrt
+    }
+
+    fn start_yaw(&self) -> f32 {
+        self.start_yaw
+    }
+
+    fn goal_yaw(&self) -> f32 {
+        self.goal_yaw
+    }
+
+    fn tree(&self) -> &PathTree<PoseNode> {
+        &self.node_tree
+    }
+
+    fn tree_mut(&mut self) -> &mut PathTree<PoseNode> {
+        &mut self.node_tree
+    }
+
+    fn steer_pose(
+        &self,
+        from_node: &PoseNode,
+        to_node: &PoseNode,
+        max_length: f32,
+        index: usize,
+    ) -> Option<PoseNode> {
+        self.steer(from_node, to_node, max_length, index)
+    }
+}
+
+impl<'a> PoseRRTStar<'a> for ReedsSheppRRTStar<'a> {
+    fn connect_circle_dist(&self) -> f32 {
+        self.connect_circle_dist
+    }
+
+    fn search_until_max(&self) -> bool {
+        self.search_until_max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_on_a_goal_it_can_find() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (20.0, 20.0),
+        };
+        for seed in 0..10 {
+            let mut planner = ReedsSheppRRTStar::new(
+                (2.0, 2.0, 0.0),
+                (18.0, 18.0, PI),
+                vec![],
+                1.0,
+                2.0,
+                3.0,
+                0.1,
+                10,
+                2000,
+                area,
+                20.0,
+                false,
+            )
+            .with_seed(seed);
+            let path = planner.plan_gears().expect("goal connection was found");
+            assert_eq!(*path.poses.first().unwrap(), (18.0, 18.0, PI));
+            assert_eq!(path.gears.len() + 1, path.poses.len());
+        }
+    }
+}
@@ -0,0 +1,868 @@
+/// SVG rendering of scenes, trees and paths, to look at plans
+use crate::bound::*;
+use crate::math::{euclidean_distance, subtract, Point2D};
+use crate::path::Path2D;
+use crate::scene::{Scene, SceneObstacle};
+use crate::tree::PathTree;
+use crate::{Node, RRTNode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// fill and stroke of an element, colors are any svg color or "none" (escaped into the attributes)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub fill: String,
+    pub stroke: String,
+    /// in pixels
+    pub stroke_width: f32,
+    pub opacity: f32,
+}
+
+impl Style {
+    pub fn new(fill: &str, stroke: &str, stroke_width: f32) -> Self {
+        Self {
+            fill: fill.to_string(),
+            stroke: stroke.to_string(),
+            stroke_width,
+            opacity: 1.0,
+        }
+    }
+
+    fn attributes(&self) -> String {
+        format!(
+            r#"fill="{}" stroke="{}" stroke-width="{}" opacity="{}""#,
+            escape(&self.fill),
+            escape(&self.stroke),
+            self.stroke_width,
+            self.opacity
+        )
+    }
+
+    fn fill_only(&self) -> Self {
+        Self {
+            stroke: "none".to_string(),
+            ..self.clone()
+        }
+    }
+
+    fn stroke_only(&self) -> Self {
+        Self {
+            fill: "none".to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+/// attribute value with the xml special characters replaced by entities
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// image scale and the style of every kind of element
+#[derive(Debug, Clone)]
+pub struct SvgStyle {
+    /// pixels per meter
+    pub scale: f32,
+    /// pixels around the drawn area
+    pub margin: f32,
+    /// cell size (meters) of obstacles drawn by sampling
+    pub resolution: f32,
+    /// radius of the start and goal markers, in pixels
+    pub marker_radius: f32,
+    pub background: String,
+    pub explore_area: Style,
+    pub obstacle: Style,
+    pub tree: Style,
+    pub path: Style,
+    pub smooth_path: Style,
+    pub start: Style,
+    pub goal: Style,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self {
+            scale: 50.0,
+            margin: 10.0,
+            resolution: 0.05,
+            marker_radius: 5.0,
+            background: "white".to_string(),
+            explore_area: Style::new("none", "black", 1.0),
+            obstacle: Style::new("gray", "dimgray", 1.0),
+            tree: Style::new("none", "#1f77b4", 0.5),
+            path: Style::new("none", "#d62728", 2.0),
+            smooth_path: Style::new("none", "#2ca02c", 2.0),
+            start: Style::new("#1f77b4", "none", 0.0),
+            goal: Style::new("#ff7f0e", "none", 0.0),
+        }
+    }
+}
+
+/// maps world coordinates to pixels, y points up in the world and down in the image
+#[derive(Debug, Clone, Copy)]
+pub struct SvgView {
+    /// world area inside the margins
+    pub area: RectangleBounds,
+    pub scale: f32,
+    pub margin: f32,
+    pub resolution: f32,
+}
+
+impl SvgView {
+    pub fn width(&self) -> f32 {
+        (self.area.max_pt.0 - self.area.min_pt.0) * self.scale + 2.0 * self.margin
+    }
+
+    pub fn height(&self) -> f32 {
+        (self.area.max_pt.1 - self.area.min_pt.1) * self.scale + 2.0 * self.margin
+    }
+
+    /// pixel of a world point
+    pub fn point(&self, p: &Point2D) -> Point2D {
+        (
+            (p.0 - self.area.min_pt.0) * self.scale + self.margin,
+            (self.area.max_pt.1 - p.1) * self.scale + self.margin,
+        )
+    }
+
+    pub fn length(&self, l: f32) -> f32 {
+        l * self.scale
+    }
+
+    /// world area covered by the whole image, margins included
+    pub fn visible_area(&self) -> RectangleBounds {
+        let m = self.margin / self.scale;
+        RectangleBounds {
+            min_pt: (self.area.min_pt.0 - m, self.area.min_pt.1 - m),
+            max_pt: (self.area.max_pt.0 + m, self.area.max_pt.1 + m),
+        }
+    }
+
+    fn points(&self, points: &[Point2D]) -> String {
+        points
+            .iter()
+            .map(|p| {
+                let (x, y) = self.point(p);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// closed subpath through the points
+    fn ring(&self, points: &[Point2D]) -> String {
+        if points.is_empty() {
+            return String::new();
+        }
+        format!("M{}Z", self.points(points).replace(' ', "L"))
+    }
+}
+
+/// shapes that know how to draw their area
+pub trait SvgShape {
+    /// svg element(s) of the area, an empty string if there is nothing to draw
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String;
+}
+
+/// any area, drawn as the cells of the image whose centers collide
+///
+/// the cells of a row are merged into rectangles, edges are as exact as `view.resolution`
+pub fn sampled_element(shape: &dyn Collision, view: &SvgView, style: &Style) -> String {
+    let mut area = view.visible_area();
+    if let Some(b) = shape.bounding_box() {
+        area.min_pt = (area.min_pt.0.max(b.min_pt.0), area.min_pt.1.max(b.min_pt.1));
+        area.max_pt = (area.max_pt.0.min(b.max_pt.0), area.max_pt.1.min(b.max_pt.1));
+    }
+    let res = view.resolution;
+    if res <= 0.0 || area.max_pt.0 < area.min_pt.0 || area.max_pt.1 < area.min_pt.1 {
+        return String::new();
+    }
+    let nx = ((area.max_pt.0 - area.min_pt.0) / res).ceil().max(1.0) as usize;
+    let ny = ((area.max_pt.1 - area.min_pt.1) / res).ceil().max(1.0) as usize;
+
+    let mut d = String::new();
+    for j in 0..ny {
+        let y = area.min_pt.1 + j as f32 * res;
+        let mut run: Option<usize> = None;
+        for i in 0..=nx {
+            let hit = i < nx && shape.is_collision(&(area.min_pt.0 + (i as f32 + 0.5) * res, y + res / 2.0));
+            match (hit, run) {
+                (true, None) => run = Some(i),
+                (false, Some(first)) => {
+                    let (px, py) = view.point(&(area.min_pt.0 + first as f32 * res, y + res));
+                    let (w, h) = (view.length((i - first) as f32 * res), view.length(res));
+                    d.push_str(&format!("M{:.2} {:.2}h{:.2}v{:.2}h{:.2}z", px, py, w, h, -w));
+                    run = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    if d.is_empty() {
+        return d;
+    }
+    format!(r#"<path d="{}" {}/>"#, d, style.fill_only().attributes())
+}
+
+impl SvgShape for RectangleBounds {
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let (x, y) = view.point(&(self.min_pt.0, self.max_pt.1));
+        format!(
+            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" {}/>"#,
+            x,
+            y,
+            view.length(self.max_pt.0 - self.min_pt.0),
+            view.length(self.max_pt.1 - self.min_pt.1),
+            style.attributes()
+        )
+    }
+}
+
+impl SvgShape for CircleBounds {
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let (x, y) = view.point(&self.center_pt);
+        format!(
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#,
+            x,
+            y,
+            view.length(self.radius),
+            style.attributes()
+        )
+    }
+}
+
+impl SvgShape for OrientedRectangleBounds {
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        format!(r#"<polygon points="{}" {}/>"#, view.points(&self.corners()), style.attributes())
+    }
+}
+
+impl SvgShape for CapsuleBounds {
+    /// two straight sides joined by half circles
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let (a, b) = (view.point(&self.start_pt), view.point(&self.end_pt));
+        let len = euclidean_distance(&subtract(&b, &a));
+        let r = view.length(self.radius);
+        if len == 0.0 {
+            return CircleBounds {
+                center_pt: self.start_pt,
+                radius: self.radius,
+            }
+            .svg_element(view, style);
+        }
+        let n = (-(b.1 - a.1) / len * r, (b.0 - a.0) / len * r);
+        format!(
+            r#"<path d="M{:.2} {:.2}L{:.2} {:.2}A{r:.2} {r:.2} 0 0 0 {:.2} {:.2}L{:.2} {:.2}A{r:.2} {r:.2} 0 0 0 {:.2} {:.2}Z" {}/>"#,
+            a.0 + n.0,
+            a.1 + n.1,
+            b.0 + n.0,
+            b.1 + n.1,
+            b.0 - n.0,
+            b.1 - n.1,
+            a.0 - n.0,
+            a.1 - n.1,
+            a.0 + n.0,
+            a.1 + n.1,
+            style.attributes()
+        )
+    }
+}
+
+impl SvgShape for SegmentWall {
+    /// a line, only the stroke shows
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let (a, b) = (view.point(&self.start_pt), view.point(&self.end_pt));
+        format!(
+            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" {}/>"#,
+            a.0,
+            a.1,
+            b.0,
+            b.1,
+            style.attributes()
+        )
+    }
+}
+
+impl SvgShape for ConvexPolygonBounds {
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        format!(r#"<polygon points="{}" {}/>"#, view.points(&self.hull()), style.attributes())
+    }
+}
+
+impl SvgShape for PolygonBounds {
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let d: String = std::iter::once(&self.exterior)
+            .chain(self.holes.iter())
+            .map(|ring| view.ring(ring))
+            .collect();
+        format!(r#"<path d="{}" fill-rule="evenodd" {}/>"#, d, style.attributes())
+    }
+}
+
+impl SvgShape for TrackCorridor {
+    /// everything visible outside the corridor is filled, the walls are stroked
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        let rings = if self.closed {
+            view.ring(&self.left) + &view.ring(&self.right)
+        } else {
+            let ring: Vec<Point2D> = self.left.iter().chain(self.right.iter().rev()).copied().collect();
+            view.ring(&ring)
+        };
+        let v = view.visible_area();
+        let outside = view.ring(&[v.min_pt, (v.max_pt.0, v.min_pt.1), v.max_pt, (v.min_pt.0, v.max_pt.1)]);
+        format!(
+            r#"<path d="{}{}" fill-rule="evenodd" {}/><path d="{}" {}/>"#,
+            outside,
+            rings,
+            style.fill_only().attributes(),
+            rings,
+            style.stroke_only().attributes()
+        )
+    }
+}
+
+impl SvgShape for SceneObstacle {
+    /// combined obstacles are sampled, invalid ones are skipped
+    fn svg_element(&self, view: &SvgView, style: &Style) -> String {
+        match self {
+            SceneObstacle::Circle(c) => c.svg_element(view, style),
+            SceneObstacle::Rectangle(r) => r.svg_element(view, style),
+            SceneObstacle::OrientedRectangle(r) => r.svg_element(view, style),
+            SceneObstacle::Capsule(c) => c.svg_element(view, style),
+            SceneObstacle::SegmentWall(w) => w.svg_element(view, style),
+            SceneObstacle::ConvexPolygon { points } if points.len() >= 3 => ConvexPolygonBounds::new_from_points(points)
+                .map_or_else(String::new, |p| p.svg_element(view, style)),
+            SceneObstacle::ConvexPolygon { .. } => String::new(),
+            SceneObstacle::Polygon { exterior, holes } => PolygonBounds::new_with_holes(exterior, holes)
+                .map_or_else(String::new, |p| p.svg_element(view, style)),
+            SceneObstacle::TrackCorridor {
+                centreline,
+                left_widths,
+                right_widths,
+                closed,
+            } => TrackCorridor::new(centreline, left_widths, right_widths, *closed)
+                .map_or_else(String::new, |t| t.svg_element(view, style)),
+            _ => self
+                .build()
+                .map_or_else(|_| String::new(), |o| sampled_element(o.as_ref(), view, style)),
+        }
+    }
+}
+
+/// SVG Renderer
+/// --
+///
+/// collects elements over a world area in drawing order (later ones on top) and writes them
+/// as one svg document. Shapes implementing SvgShape are drawn exactly, any other Collision
+/// (grids, worlds, combinations) by sampling.
+pub struct SvgRenderer {
+    /// world area drawn inside the margins
+    pub area: RectangleBounds,
+    pub style: SvgStyle,
+    elements: Vec<String>,
+}
+
+impl SvgRenderer {
+    /// empty image of the area with the default style
+    pub fn new(area: RectangleBounds) -> Self {
+        Self::with_style(area, SvgStyle::default())
+    }
+
+    pub fn with_style(area: RectangleBounds, style: SvgStyle) -> Self {
+        Self {
+            area,
+            style,
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn view(&self) -> SvgView {
+        SvgView {
+            area: self.area,
+            scale: self.style.scale,
+            margin: self.style.margin,
+            resolution: self.style.resolution,
+        }
+    }
+
+    /// add any element, e.g. text or shapes with a custom style
+    pub fn element(&mut self, element: String) {
+        if !element.is_empty() {
+            self.elements.push(element);
+        }
+    }
+
+    /// explore area, start, goal and obstacles of a scene
+    pub fn scene(&mut self, scene: &Scene) {
+        self.explore_area(&scene.explore_area);
+        for obstacle in &scene.obstacles {
+            self.obstacle(obstacle);
+        }
+        self.start(&scene.start);
+        self.goal(&scene.goal);
+    }
+
+    pub fn explore_area(&mut self, area: &RectangleBounds) {
+        let element = area.svg_element(&self.view(), &self.style.explore_area);
+        self.element(element);
+    }
+
+    pub fn obstacle(&mut self, obstacle: &dyn SvgShape) {
+        let element = obstacle.svg_element(&self.view(), &self.style.obstacle);
+        self.element(element);
+    }
+
+    /// obstacle without a known shape, drawn by sampling
+    pub fn obstacle_sampled(&mut self, obstacle: &dyn Collision) {
+        let element = sampled_element(obstacle, &self.view(), &self.style.obstacle);
+        self.element(element);
+    }
+
+    /// every edge of the tree
+    pub fn tree<T: Node + AsRef<RRTNode>>(&mut self, tree: &PathTree<T>) {
+        self.tree_nodes(tree.node_list().into_iter().map(|n| n.as_ref()));
+    }
+
+    /// edges of tree nodes along their paths, or straight from the parent without a path
+    pub fn tree_nodes<'n>(&mut self, nodes: impl IntoIterator<Item = &'n RRTNode>) {
+        let nodes: Vec<&RRTNode> = nodes.into_iter().collect();
+        let points: HashMap<usize, Point2D> = nodes.iter().map(|n| (n.id, n.point)).collect();
+        let view = self.view();
+        let mut d = String::new();
+        for node in nodes {
+            let edge = if node.path.len() >= 2 {
+                node.path.clone()
+            } else {
+                match node.parent_id.and_then(|id| points.get(&id)) {
+                    Some(parent) => vec![*parent, node.point],
+                    None => continue,
+                }
+            };
+            d.push_str(&format!("M{}", view.points(&edge).replace(' ', "L")));
+        }
+        if !d.is_empty() {
+            self.element(format!(r#"<path d="{}" {}/>"#, d, self.style.tree.stroke_only().attributes()));
+        }
+    }
+
+    pub fn path(&mut self, path: &Path2D) {
+        let style = self.style.path.clone();
+        self.polyline(&path.0, &style);
+    }
+
+    pub fn smooth_path(&mut self, path: &Path2D) {
+        let style = self.style.smooth_path.clone();
+        self.polyline(&path.0, &style);
+    }
+
+    pub fn polyline(&mut self, points: &[Point2D], style: &Style) {
+        if points.is_empty() {
+            return;
+        }
+        let element = format!(
+            r#"<polyline points="{}" {}/>"#,
+            self.view().points(points),
+            style.attributes()
+        );
+        self.element(element);
+    }
+
+    pub fn start(&mut self, pt: &Point2D) {
+        let style = self.style.start.clone();
+        self.marker(pt, &style);
+    }
+
+    pub fn goal(&mut self, pt: &Point2D) {
+        let style = self.style.goal.clone();
+        self.marker(pt, &style);
+    }
+
+    /// dot with the marker radius
+    pub fn marker(&mut self, pt: &Point2D, style: &Style) {
+        let (x, y) = self.view().point(pt);
+        let element = format!(
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{}" {}/>"#,
+            x,
+            y,
+            self.style.marker_radius,
+            style.attributes()
+        );
+        self.element(element);
+    }
+
+    pub fn to_svg(&self) -> String {
+        let view = self.view();
+        let (w, h) = (view.width(), view.height());
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\">\n"
+        );
+        svg.push_str(&format!(
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+            escape(&self.style.background)
+        ));
+        svg.push_str("<g stroke-linejoin=\"round\" stroke-linecap=\"round\">\n");
+        for element in &self.elements {
+            svg.push_str(element);
+            svg.push('\n');
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(area: RectangleBounds, scale: f32, margin: f32, resolution: f32) -> SvgView {
+        SvgView {
+            area,
+            scale,
+            margin,
+            resolution,
+        }
+    }
+
+    fn square(size: f32) -> RectangleBounds {
+        RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (size, size),
+        }
+    }
+
+    /// value of the first attribute with this name
+    fn attribute<'s>(element: &'s str, name: &str) -> &'s str {
+        let key = format!(" {}=\"", name);
+        let start = element.find(&key).expect("attribute") + key.len();
+        &element[start..start + element[start..].find('"').unwrap()]
+    }
+
+    /// path data split into commands and their numbers
+    fn commands(d: &str) -> Vec<(char, Vec<f32>)> {
+        let mut commands: Vec<(char, String)> = Vec::new();
+        for c in d.chars() {
+            match commands.last_mut() {
+                Some((_, args)) if !c.is_ascii_alphabetic() => args.push(c),
+                _ => commands.push((c, String::new())),
+            }
+        }
+        commands
+            .into_iter()
+            .map(|(c, args)| {
+                let numbers = args
+                    .split(|c: char| c == ' ' || c == ',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse().unwrap())
+                    .collect();
+                (c, numbers)
+            })
+            .collect()
+    }
+
+    /// world point of a pixel
+    fn world(view: &SvgView, p: Point2D) -> Point2D {
+        (
+            (p.0 - view.margin) / view.scale + view.area.min_pt.0,
+            view.area.max_pt.1 - (p.1 - view.margin) / view.scale,
+        )
+    }
+
+    fn near(a: Point2D, b: Point2D) -> bool {
+        euclidean_distance(&subtract(&a, &b)) < 1e-2
+    }
+
+    #[test]
+    fn view_flips_y() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 5.0),
+        };
+        let v = view(area, 10.0, 5.0, 0.1);
+        assert_eq!((v.width(), v.height()), (110.0, 60.0));
+        assert_eq!(v.point(&(0.0, 0.0)), (5.0, 55.0));
+        assert_eq!(v.point(&(10.0, 5.0)), (105.0, 5.0));
+        assert_eq!(v.point(&(2.0, 1.0)), (25.0, 45.0));
+        // the top left corner of a rectangle is its highest point
+        let rect = RectangleBounds {
+            min_pt: (1.0, 1.0),
+            max_pt: (3.0, 2.0),
+        };
+        assert!(rect
+            .svg_element(&v, &Style::new("gray", "none", 0.0))
+            .starts_with(r#"<rect x="15.00" y="35.00" width="20.00" height="10.00""#));
+    }
+
+    #[test]
+    fn capsule_arcs_bulge_outwards() {
+        let v = view(square(10.0), 50.0, 10.0, 0.05);
+        let capsules = [
+            ((2.0, 5.0), (6.0, 5.0)),
+            ((3.0, 2.0), (7.0, 8.0)),
+            ((6.0, 5.0), (2.0, 5.0)),
+        ];
+        for (start_pt, end_pt) in capsules {
+            let capsule = CapsuleBounds {
+                start_pt,
+                end_pt,
+                radius: 1.0,
+            };
+            let element = capsule.svg_element(&v, &Style::new("gray", "none", 0.0));
+            let mut current = (0.0, 0.0);
+            let mut middles = Vec::new();
+            for (c, args) in commands(attribute(&element, "d")) {
+                if c == 'A' {
+                    // half circles, centred between the ends
+                    let end = (args[5], args[6]);
+                    let centre = ((current.0 + end.0) / 2.0, (current.1 + end.1) / 2.0);
+                    let r = subtract(&current, &centre);
+                    let middle = if args[4] == 1.0 {
+                        (centre.0 - r.1, centre.1 + r.0)
+                    } else {
+                        (centre.0 + r.1, centre.1 - r.0)
+                    };
+                    middles.push(world(&v, middle));
+                }
+                if args.len() >= 2 {
+                    current = (args[args.len() - 2], args[args.len() - 1]);
+                }
+            }
+            // the middle of each arc lies a radius beyond its end, away from the other end
+            let d = subtract(&end_pt, &start_pt);
+            let len = euclidean_distance(&d);
+            let dir = (d.0 / len, d.1 / len);
+            assert_eq!(middles.len(), 2);
+            assert!(near(middles[0], (end_pt.0 + dir.0, end_pt.1 + dir.1)), "{:?}", middles);
+            assert!(near(middles[1], (start_pt.0 - dir.0, start_pt.1 - dir.1)), "{:?}", middles);
+        }
+    }
+
+    #[test]
+    fn track_corridor_fills_the_outside() {
+        let v = view(square(10.0), 50.0, 10.0, 0.05);
+        let corridors = [
+            TrackCorridor::new_constant_width(
+                &[(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)],
+                1.0,
+                1.0,
+                true,
+            ),
+            TrackCorridor::new_constant_width(
+                &[(1.0, 5.0), (5.0, 5.0), (9.0, 7.0)],
+                1.0,
+                1.5,
+                false,
+            ),
+        ];
+        for corridor in corridors {
+            let corridor = corridor.unwrap();
+            let element = corridor.svg_element(&v, &Style::new("gray", "dimgray", 1.0));
+            let (fill, walls) = element.split_at(element.find("/>").unwrap() + 2);
+            assert_eq!(attribute(fill, "fill-rule"), "evenodd");
+            assert_eq!(attribute(fill, "stroke"), "none");
+            assert_eq!(attribute(walls, "fill"), "none");
+
+            let mut rings: Vec<Vec<Point2D>> = Vec::new();
+            for (c, args) in commands(attribute(fill, "d")) {
+                match c {
+                    'M' => rings.push(vec![(args[0], args[1])]),
+                    'L' => rings.last_mut().unwrap().push((args[0], args[1])),
+                    _ => {}
+                }
+            }
+            // the outside of the image plus the walls
+            assert_eq!(rings.len(), if corridor.closed { 3 } else { 2 });
+
+            let crossings = |p: Point2D, ring: &Vec<Point2D>| {
+                (0..ring.len())
+                    .filter(|&i| {
+                        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                        (a.1 > p.1) != (b.1 > p.1)
+                            && p.0 < a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0)
+                    })
+                    .count()
+            };
+            for i in 0..30 {
+                for j in 0..30 {
+                    let pt = (-0.1 + i as f32 * 0.35, -0.1 + j as f32 * 0.35);
+                    if corridor.signed_distance(&pt).abs() < 0.01 {
+                        continue;
+                    }
+                    let px = v.point(&pt);
+                    let crossed: usize = rings.iter().map(|ring| crossings(px, ring)).sum();
+                    let filled = crossed % 2 == 1;
+                    assert_eq!(filled, corridor.is_collision(&pt), "{:?}", pt);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_rows_are_merged() {
+        let v = view(square(4.0), 10.0, 0.0, 0.5);
+        let style = Style::new("gray", "dimgray", 1.0);
+        let rect = RectangleBounds {
+            min_pt: (1.0, 1.0),
+            max_pt: (3.0, 2.0),
+        };
+        assert_eq!(
+            sampled_element(&rect, &v, &style),
+            r#"<path d="M10.00 25.00h20.00v5.00h-20.00zM10.00 20.00h20.00v5.00h-20.00z" fill="gray" stroke="none" stroke-width="1" opacity="1"/>"#
+        );
+
+        // one run per row, two where the rows cross the hole
+        let ring = |a: f32, b: f32| vec![(a, a), (b, a), (b, b), (a, b)];
+        let frame = PolygonBounds::new_with_holes(&ring(0.0, 4.0), &[ring(1.0, 3.0)]).unwrap();
+        let element = sampled_element(&frame, &v, &style);
+        let commands = commands(attribute(&element, "d"));
+        let runs: Vec<&Vec<f32>> =
+            commands.iter().filter(|(c, _)| *c == 'M').map(|(_, a)| a).collect();
+        assert_eq!(runs.len(), 2 + 4 * 2 + 2);
+        let widths: f32 =
+            commands.iter().filter(|(c, _)| *c == 'h').map(|(_, a)| a[0].max(0.0)).sum();
+        // the area of the frame, 16 - 4 square meters
+        assert_eq!(widths * v.length(v.resolution), 12.0 * v.length(1.0) * v.length(1.0));
+        // nothing outside the shape, nothing at all when it's out of sight
+        assert!(runs.iter().all(|m| m[0] >= 0.0 && m[0] < 40.0 && m[1] >= 0.0 && m[1] < 40.0));
+        let far = CircleBounds {
+            center_pt: (20.0, 20.0),
+            radius: 1.0,
+        };
+        assert_eq!(sampled_element(&far, &v, &style), "");
+    }
+
+    #[test]
+    fn scene_tree_and_path() {
+        let mut scene = Scene::new(
+            square(10.0),
+            (1.0, 1.0),
+            (9.0, 9.0),
+            crate::scene::PlannerConfig::RRT {
+                expand_dis: 0.5,
+                path_resolution: 0.1,
+                goal_sample_rate: 5,
+                max_iter: 100,
+            },
+        );
+        let circle = SceneObstacle::Circle(CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 1.0,
+        });
+        let rectangle = SceneObstacle::Rectangle(RectangleBounds {
+            min_pt: (1.0, 2.0),
+            max_pt: (2.0, 3.0),
+        });
+        scene.obstacles = vec![
+            rectangle.clone(),
+            circle.clone(),
+            SceneObstacle::OrientedRectangle(OrientedRectangleBounds {
+                center_pt: (7.0, 2.0),
+                half_extents: (1.0, 0.5),
+                yaw: 0.3,
+            }),
+            SceneObstacle::Capsule(CapsuleBounds {
+                start_pt: (1.0, 8.0),
+                end_pt: (3.0, 8.0),
+                radius: 0.4,
+            }),
+            SceneObstacle::SegmentWall(SegmentWall {
+                start_pt: (8.0, 6.0),
+                end_pt: (8.0, 9.0),
+            }),
+            SceneObstacle::ConvexPolygon {
+                points: vec![(3.0, 3.0), (4.0, 3.0), (3.5, 4.0)],
+            },
+            SceneObstacle::Polygon {
+                exterior: vec![(4.0, 0.0), (7.0, 0.0), (7.0, 3.0)],
+                holes: vec![],
+            },
+            SceneObstacle::TrackCorridor {
+                centreline: vec![(0.0, 5.0), (10.0, 5.0)],
+                left_widths: vec![4.0; 2],
+                right_widths: vec![4.0; 2],
+                closed: false,
+            },
+            SceneObstacle::Union {
+                children: vec![circle, rectangle],
+            },
+        ];
+
+        let style = SvgStyle {
+            scale: 10.0,
+            margin: 0.0,
+            ..SvgStyle::default()
+        };
+        let mut renderer = SvgRenderer::with_style(scene.explore_area, style);
+        renderer.scene(&scene);
+        let mut tree = PathTree::new();
+        tree.add_node(RRTNode::new((0.0, 0.0)));
+        tree.add_node(RRTNode {
+            id: 1,
+            parent_id: Some(0),
+            point: (1.0, 0.0),
+            path: vec![],
+        });
+        tree.add_node(RRTNode {
+            id: 2,
+            parent_id: Some(1),
+            point: (1.0, 1.0),
+            path: vec![(1.0, 0.0), (1.0, 0.5), (1.0, 1.0)],
+        });
+        renderer.tree(&tree);
+        renderer.path(&Path2D(vec![(1.0, 1.0), (0.0, 0.0)]));
+        let svg = renderer.to_svg();
+
+        let tags: Vec<&str> = svg
+            .split('<')
+            .skip(1)
+            .map(|s| &s[..s.find(|c: char| c == ' ' || c == '>').unwrap()])
+            .collect();
+        let expected = [
+            "svg", "rect", "g", // background
+            "rect", // explore area
+            "rect", "circle", "polygon", "path", "line", "polygon", "path", "path", "path",
+            "path", // union, sampled
+            "circle", "circle", // start and goal
+            "path", "polyline", "/g", "/svg",
+        ];
+        assert_eq!(tags, expected);
+
+        let line = |prefix: &str| svg.lines().find(|l| l.starts_with(prefix)).unwrap().to_string();
+        assert_eq!(
+            line("<path d=\"M0.00,100.00L10.00"),
+            r##"<path d="M0.00,100.00L10.00,100.00M10.00,100.00L10.00,95.00L10.00,90.00" fill="none" stroke="#1f77b4" stroke-width="0.5" opacity="1"/>"##
+        );
+        assert_eq!(
+            line("<polyline"),
+            r##"<polyline points="10.00,90.00 0.00,100.00" fill="none" stroke="#d62728" stroke-width="2" opacity="1"/>"##
+        );
+        assert_eq!(
+            line("<line"),
+            r#"<line x1="80.00" y1="40.00" x2="80.00" y2="10.00" fill="gray" stroke="dimgray" stroke-width="1" opacity="1"/>"#
+        );
+        assert_eq!(
+            line("<circle cx=\"10.00\""),
+            r##"<circle cx="10.00" cy="90.00" r="5" fill="#1f77b4" stroke="none" stroke-width="0" opacity="1"/>"##
+        );
+    }
+
+    #[test]
+    fn styles_are_escaped() {
+        let style = Style::new("red\" onload=\"alert(1)", "<b>&", 1.0);
+        assert_eq!(
+            style.attributes(),
+            r#"fill="red&quot; onload=&quot;alert(1)" stroke="&lt;b&gt;&amp;" stroke-width="1" opacity="1""#
+        );
+        let mut renderer = SvgRenderer::new(square(1.0));
+        renderer.style.background = "<x>".to_string();
+        assert!(renderer.to_svg().contains(r#"fill="&lt;x&gt;""#));
+    }
+}
@@ -1,26 +1,37 @@
 use crate::PathTree;
 /// Rapidly Exploring Random Trees (Simple)
 use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
 use crate::math::*;
 use crate::path::Path2D;
 use crate::planner::Planner;
 use crate::rrtnode::Node;
 use crate::rrtnode::RRTNode;
-use rand::distributions::Uniform;
-use rand::{rngs::ThreadRng, thread_rng, Rng};
+use crate::sampler::{GoalBiasedSampler, Sampler, UniformSampler};
+use rand::{rngs::StdRng, SeedableRng};
 
 /// RRT Configuration Object
 pub struct RRT<'a> {
     pub start: Point2D,
     pub goal: Point2D,
     pub obstacles: Vec<&'a dyn Collision>,
+    /// robot shape checked against the obstacles, a point by default
+    pub footprint: Footprint,
+    /// obstacles moving over time, a node is reached at its arc length over nominal_speed
+    pub dynamic_obstacles: Vec<&'a dyn DynamicObstacle>,
+    pub nominal_speed: f32,
     pub expand_dis: f32,
     pub path_resolution: f32,
+    /// percent of the default samples that go for the goal
+    #[deprecated(note = "set `sampler` to a GoalBiasedSampler instead")]
     pub goal_sample_rate: u32,
     pub max_iter: u32,
     pub explore_area: RectangleBounds,
     pub node_tree: PathTree<RRTNode>,
-    rng: ThreadRng,
+    /// None samples uniformly over the explore area, goal biased by goal_sample_rate
+    pub sampler: Option<Box<dyn Sampler + 'a>>,
+    pub(crate) rng: StdRng,
 }
 
 impl Planner<'_> for RRT<'_> {
@@ -29,6 +40,18 @@ impl Planner<'_> for RRT<'_> {
         &self.obstacles
     }
 
+    fn footprint(&self) -> Footprint {
+        self.footprint
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        &self.dynamic_obstacles
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.nominal_speed
+    }
+
     /// RRT Path Planning
     fn plan(&mut self) -> Option<Path2D> {
         // start by introdcing the start node to the node list
@@ -64,6 +87,12 @@ impl Planner<'_> for RRT<'_> {
                             .get(parent_id as usize)
                             .expect("RRT Parent Node failed to get from node list");
                         self.is_collision_segment(&parent_node.point, &new_node.point)
+                            || (!self.dynamic_obstacles.is_empty()
+                                && self.is_collision_segment_timed(
+                                    &parent_node.point,
+                                    &new_node.point,
+                                    self.arc_length(parent_id),
+                                ))
                     }
                 }
             };
@@ -100,6 +129,7 @@ impl Planner<'_> for RRT<'_> {
 
 impl<'a> RRT<'a> {
     /// create new tree, leave other parameters open
+    #[allow(deprecated)]
     pub fn new(
         start: Point2D,
         goal: Point2D,
@@ -115,28 +145,48 @@ impl<'a> RRT<'a> {
             start: start,
             goal: goal,
             obstacles: obstacles,
+            footprint: Footprint::Point,
+            dynamic_obstacles: Vec::new(),
+            nominal_speed: 1.0,
             expand_dis: expand_dis,
             path_resolution: path_resolution,
             goal_sample_rate: goal_sample_rate,
             max_iter: max_iter,
             explore_area: explore_area,
-            //robot_radius: 0.0,
             node_tree: PathTree::new(),
-            rng: thread_rng(),
+            sampler: None,
+            rng: StdRng::from_entropy(),
         }
     }
 
-    /// generate random node (exploration), sometimes going for the goal node (goal sampling rate)
-    pub fn get_random_node(&mut self, end: &RRTNode) -> RRTNode {
-        // TODO: make this more efficient with rng setup
-        let percent = self.rng.gen_range(0..100);
-        if percent <= self.goal_sample_rate {
-            RRTNode::new(end.point)
-        } else {
-            let uniform_x = Uniform::new(self.explore_area.min_pt.0, self.explore_area.max_pt.0);
-            let uniform_y = Uniform::new(self.explore_area.min_pt.1, self.explore_area.max_pt.1);
-            RRTNode::new((self.rng.sample(&uniform_x), self.rng.sample(&uniform_y)))
+    /// length of the tree path from the start to a node
+    pub fn arc_length(&self, id: usize) -> f32 {
+        let mut length = 0.0;
+        let mut node = self.node_tree.get(id);
+        while let Some(n) = node {
+            node = n.parent_id.and_then(|p| self.node_tree.get(p));
+            if let Some(parent) = node {
+                length += n.distance_between(parent);
+            }
         }
+        length
+    }
+
+    /// seed the random source, identical inputs and seed give identical trees and paths
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// generate random node from the sampler, goal biased samplers pull toward end
+    pub fn get_random_node(&mut self, end: &RRTNode) -> RRTNode {
+        let point = match self.sampler.as_mut() {
+            Some(sampler) => sampler.sample(&end.point, &mut self.rng),
+            #[allow(deprecated)]
+            None => GoalBiasedSampler::new(UniformSampler::new(self.explore_area), self.goal_sample_rate)
+                .sample(&end.point, &mut self.rng),
+        };
+        RRTNode::new(point)
     }
 
     /// grow out a path from node to node
@@ -186,3 +236,55 @@ impl<'a> RRT<'a> {
         new_node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn goal_sample_rate_drives_the_default_sampler() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let goal = RRTNode::new((9.0, 9.0));
+        let mut rrt = RRT::new((1.0, 1.0), goal.point, vec![], 1.0, 0.1, 0, 100, area).with_seed(0);
+        // set after new, as before there was a sampler field
+        rrt.goal_sample_rate = 100;
+        assert!((0..50).all(|_| rrt.get_random_node(&goal).point == goal.point));
+
+        rrt.sampler = Some(Box::new(UniformSampler::new(area)));
+        assert!((0..50).all(|_| rrt.get_random_node(&goal).point != goal.point));
+    }
+
+    #[test]
+    fn seeded_plans_repeat() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        let run = |seed| {
+            let mut rrt = RRT::new((1.0, 1.0), (9.0, 9.0), vec![&circle], 0.5, 0.1, 5, 2000, area)
+                .with_seed(seed);
+            let path = rrt.plan().map(|p| p.0);
+            let tree: Vec<(Point2D, Option<usize>)> =
+                rrt.node_tree.node_list().iter().map(|n| (n.point, n.parent_id)).collect();
+            (path, tree)
+        };
+
+        let (path, tree) = run(3);
+        assert!(path.is_some());
+        assert_eq!((path.clone(), tree.clone()), run(3));
+        assert_ne!(tree, run(4).1);
+
+        // pinned so that changes to the search show up
+        let length = |seed| Path2D(run(seed).0.unwrap()).path_length();
+        assert!((length(3) - 16.620655).abs() < 1e-4);
+        assert!((length(4) - 14.453167).abs() < 1e-4);
+    }
+}
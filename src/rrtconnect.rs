@@ -0,0 +1,277 @@
+use crate::PathTree;
+/// Bidirectional Rapidly Exploring Random Trees (RRT-Connect)
+use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::math::*;
+use crate::path::Path2D;
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::RRTNode;
+
+/// result of growing a tree toward a target
+enum Extend {
+    /// the edge collided or left the explore area
+    Trapped,
+    /// a node was added, but the target wasn't reached
+    Advanced(usize),
+    /// the node with this id sits on the target
+    Reached(usize),
+}
+
+/// RRT-Connect Configuration Object
+/// --
+///
+/// grows one tree from the start and one from the goal. Each iteration one tree
+/// takes a step toward a random sample and the other greedily tries to connect to
+/// the new node, then the trees trade roles.
+pub struct RRTConnect<'a> {
+    pub rrt: RRT<'a>,
+    pub start_tree: PathTree<RRTNode>,
+    pub goal_tree: PathTree<RRTNode>,
+}
+
+impl Planner<'_> for RRTConnect<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
+    /// RRT-Connect Path Planning
+    fn plan(&mut self) -> Option<Path2D> {
+        let start_node = RRTNode::new(self.rrt.start);
+        let goal_node = RRTNode::new(self.rrt.goal);
+        self.start_tree.add_node(start_node.clone());
+        self.goal_tree.add_node(goal_node.clone());
+
+        // whether the goal tree is the one being extended this iteration
+        let mut swapped = false;
+
+        for _idx in 1..=self.rrt.max_iter {
+            let (tree_a, tree_b, other_root) = if swapped {
+                (&mut self.goal_tree, &mut self.start_tree, &start_node)
+            } else {
+                (&mut self.start_tree, &mut self.goal_tree, &goal_node)
+            };
+
+            // the goal sampling rate biases samples toward the root of the other tree
+            let rnd_node = self.rrt.get_random_node(other_root);
+            let new_id = match Self::extend(&self.rrt, tree_a, &rnd_node, !swapped) {
+                Extend::Trapped => None,
+                Extend::Advanced(id) | Extend::Reached(id) => Some(id),
+            };
+
+            if let Some(id_a) = new_id {
+                let new_node = tree_a.get(id_a).unwrap().clone();
+                if let Some(id_b) = Self::connect(&self.rrt, tree_b, &new_node, swapped) {
+                    let (start_id, goal_id) = if swapped { (id_b, id_a) } else { (id_a, id_b) };
+                    let path = self.splice_paths(start_id, goal_id);
+                    // the goal tree is only timed once the trees meet
+                    if !self.is_collision_path_timed(&path) {
+                        return Some(path);
+                    }
+                }
+            }
+
+            swapped = !swapped;
+        }
+
+        None
+    }
+}
+
+impl<'a> RRTConnect<'a> {
+    /// create new trees, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+    ) -> Self {
+        Self {
+            rrt: RRT::new(
+                start,
+                goal,
+                obstacles,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            start_tree: PathTree::new(),
+            goal_tree: PathTree::new(),
+        }
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    /// take a single expand_dis step from the nearest node of the tree toward the target
+    ///
+    /// edges of a timed tree (grown from the start) are also checked against the moving obstacles
+    fn extend(rrt: &RRT, tree: &mut PathTree<RRTNode>, target: &RRTNode, timed: bool) -> Extend {
+        let nearest_ind = tree
+            .get_nearest_node_index(target)
+            .expect("node list should have a size > 0");
+        let nearest_node = tree
+            .get(nearest_ind)
+            .expect("RRT Nearest Node failed to get from node list");
+        if nearest_node.point == target.point {
+            return Extend::Reached(nearest_ind);
+        }
+
+        // ids match the insertion order, as in RRT::plan
+        let new_node = rrt.steer(nearest_node, target, rrt.expand_dis, tree.len());
+        // steps shorter than path_resolution make no progress, connect would never end
+        if new_node.point == nearest_node.point
+            || !rrt.explore_area.is_collision(&new_node.point)
+            || rrt.is_collision(&new_node.point)
+            || rrt.is_collision_segment(&nearest_node.point, &new_node.point)
+            || (timed
+                && !rrt.dynamic_obstacles.is_empty()
+                && rrt.is_collision_segment_timed(
+                    &nearest_node.point,
+                    &new_node.point,
+                    Path2D(tree.get_path(nearest_node, Vec::new())).path_length(),
+                ))
+        {
+            return Extend::Trapped;
+        }
+
+        let id = new_node.id;
+        let reached = new_node.point == target.point;
+        tree.add_node(new_node);
+        if reached {
+            Extend::Reached(id)
+        } else {
+            Extend::Advanced(id)
+        }
+    }
+
+    /// greedily extend the tree toward the target until it is reached (id of the meeting node) or trapped
+    fn connect(
+        rrt: &RRT,
+        tree: &mut PathTree<RRTNode>,
+        target: &RRTNode,
+        timed: bool,
+    ) -> Option<usize> {
+        loop {
+            match Self::extend(rrt, tree, target, timed) {
+                Extend::Trapped => return None,
+                Extend::Advanced(_) => continue,
+                Extend::Reached(id) => return Some(id),
+            }
+        }
+    }
+
+    /// join the branches meeting at start_id (start tree) and goal_id (goal tree)
+    ///
+    /// the path runs from the goal to the start, matching RRT::plan
+    fn splice_paths(&self, start_id: usize, goal_id: usize) -> Path2D {
+        let mut path = self
+            .goal_tree
+            .get_path(self.goal_tree.get(goal_id).unwrap(), Vec::<Point2D>::new());
+        path.reverse();
+        // both branches hold the meeting point, keep it once
+        path.pop();
+        Path2D(
+            self.start_tree
+                .get_path(self.start_tree.get(start_id).unwrap(), path),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::ConstantVelocityObstacle;
+
+    #[test]
+    fn short_steps_do_not_loop() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        // expand_dis below path_resolution, steer never moves
+        let mut rrt = RRTConnect::new((1.0, 1.0), (9.0, 9.0), vec![], 0.05, 0.1, 5, 200, area).with_seed(1);
+        assert!(rrt.plan().is_none());
+        assert_eq!(rrt.start_tree.len(), 1);
+        assert_eq!(rrt.goal_tree.len(), 1);
+    }
+
+    #[test]
+    fn seeded_plans_repeat() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        let run = |seed| {
+            let mut rrt = RRTConnect::new((1.0, 1.0), (9.0, 9.0), vec![&circle], 0.5, 0.1, 5, 2000, area)
+                .with_seed(seed);
+            let path = rrt.plan().map(|p| p.0);
+            let trees: Vec<Vec<(Point2D, Option<usize>)>> = [&rrt.start_tree, &rrt.goal_tree]
+                .iter()
+                .map(|t| t.node_list().iter().map(|n| (n.point, n.parent_id)).collect())
+                .collect();
+            (path, trees)
+        };
+
+        let (path, trees) = run(3);
+        assert!(path.is_some());
+        assert_eq!((path.clone(), trees.clone()), run(3));
+        assert_ne!(trees, run(4).1);
+
+        // pinned so that changes to the search show up
+        let length = |seed| Path2D(run(seed).0.unwrap()).path_length();
+        assert!((length(3) - 15.02411).abs() < 1e-4);
+        assert!((length(4) - 14.56481).abs() < 1e-4);
+    }
+
+    #[test]
+    fn paths_dodge_moving_obstacles() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        // crosses the straight line while the robot would drive through it
+        let shape = RectangleBounds {
+            min_pt: (-1.0, -1.0),
+            max_pt: (1.0, 1.0),
+        };
+        let crossing = ConstantVelocityObstacle::new(Box::new(shape), (5.0, 9.0), (0.0, -1.0));
+        for seed in 0..5 {
+            let mut rrt = RRTConnect::new((1.0, 5.0), (9.0, 5.0), vec![], 0.5, 0.1, 5, 2000, area)
+                .with_seed(seed);
+            rrt.rrt.dynamic_obstacles = vec![&crossing];
+            assert!(rrt.is_collision_path_timed(&Path2D(vec![(9.0, 5.0), (1.0, 5.0)])));
+
+            let path = rrt.plan().expect("a path around the box");
+            assert!(!rrt.is_collision_path_timed(&path));
+        }
+    }
+}
@@ -1,5 +1,7 @@
 /// node of randomly exploring random tree
-use crate::math::{euclidean_distance, Point2D};
+use crate::dubins::Gear;
+use crate::math::{euclidean_distance, Point2D, Pose2D};
+use crate::path::TimedState;
 use serde::{Deserialize, Serialize};
 
 
@@ -71,6 +73,31 @@ pub struct RRTStarNode {
     pub cost: f32,
 }
 
+/// node for kinematic trees--normal node + heading and cost
+///
+/// the edge from the parent is a curve, `poses` samples it with headings while
+/// `node.path` keeps the matching points. `gears[i]` is the gear driven from
+/// `poses[i]` to `poses[i + 1]`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoseNode {
+    pub node: RRTNode,
+    pub yaw: f32,
+    pub cost: f32,
+    pub poses: Vec<Pose2D>,
+    pub gears: Vec<Gear>,
+}
+
+/// node for trees over a model state--normal node + state and cost
+///
+/// `trajectory` is the simulated edge from the parent, stamped with the time since the root
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateNode {
+    pub node: RRTNode,
+    pub state: Vec<f32>,
+    pub cost: f32,
+    pub trajectory: Vec<TimedState>,
+}
+
 impl Node for RRTNode {
     /// new node (no parent and no path)
     fn new(pt: Point2D) -> Self {
@@ -108,6 +135,13 @@ impl Node for RRTNode {
     }
 }
 
+/// the tree node shared by every node type, with the edge path from the parent
+impl AsRef<RRTNode> for RRTNode {
+    fn as_ref(&self) -> &RRTNode {
+        self
+    }
+}
+
 impl Node for RRTStarNode {
     fn new(pt: Point2D) -> Self {
         Self {
@@ -136,3 +170,99 @@ impl Node for RRTStarNode {
         self.node.distance_between_pos(pos)
     }
 }
+
+impl PoseNode {
+    /// the (x, y, yaw) pose of the node
+    pub fn pose(&self) -> Pose2D {
+        (self.node.point.0, self.node.point.1, self.yaw)
+    }
+}
+
+impl Node for PoseNode {
+    fn new(pt: Point2D) -> Self {
+        Self {
+            node: RRTNode::new(pt),
+            yaw: 0.0,
+            cost: 0.0,
+            poses: Vec::<Pose2D>::with_capacity(0),
+            gears: Vec::<Gear>::with_capacity(0),
+        }
+    }
+
+    fn point(&self) -> Point2D {
+        self.node.point()
+    }
+
+    fn id(&self) -> usize {
+        self.node.id()
+    }
+
+    fn parent_id(&self) -> Option<usize> {
+        self.node.parent_id()
+    }
+
+    fn get_delta(&self, other_node: &Self) -> (f32, f32) {
+        self.node.get_delta(&other_node.node)
+    }
+
+    fn distance_between_pos(&self, pos: (f32, f32)) -> f32 {
+        self.node.distance_between_pos(pos)
+    }
+}
+
+impl StateNode {
+    /// time at which the node is reached from the root
+    pub fn time(&self) -> f32 {
+        self.trajectory.last().map_or(0.0, |s| s.t)
+    }
+}
+
+impl Node for StateNode {
+    /// node holding only a position, planners extend the state to their model
+    fn new(pt: Point2D) -> Self {
+        Self {
+            node: RRTNode::new(pt),
+            state: vec![pt.0, pt.1],
+            cost: 0.0,
+            trajectory: Vec::<TimedState>::with_capacity(0),
+        }
+    }
+
+    fn point(&self) -> Point2D {
+        self.node.point()
+    }
+
+    fn id(&self) -> usize {
+        self.node.id()
+    }
+
+    fn parent_id(&self) -> Option<usize> {
+        self.node.parent_id()
+    }
+
+    fn get_delta(&self, other_node: &Self) -> (f32, f32) {
+        self.node.get_delta(&other_node.node)
+    }
+
+    fn distance_between_pos(&self, pos: (f32, f32)) -> f32 {
+        self.node.distance_between_pos(pos)
+    }
+}
+
+impl AsRef<RRTNode> for RRTStarNode {
+    fn as_ref(&self) -> &RRTNode {
+        &self.node
+    }
+}
+
+impl AsRef<RRTNode> for PoseNode {
+    fn as_ref(&self) -> &RRTNode {
+        &self.node
+    }
+}
+
+impl AsRef<RRTNode> for StateNode {
+    fn as_ref(&self) -> &RRTNode {
+        &self.node
+    }
+}
@@ -1,5 +1,7 @@
 use crate::PathTree;
 use crate::bound::{Collision, RectangleBounds};
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
 use crate::math::Point2D;
 use crate::path::Path2D;
 use crate::planner::Planner;
@@ -22,6 +24,18 @@ impl Planner<'_> for RRTStar<'_> {
         self.rrt.obstacles()
     }
 
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
     fn plan(&mut self) -> Option<Path2D> {
         // start by introdcing the start node to the node list
         let start_node = RRTNode::new(self.rrt.start);
@@ -130,7 +144,13 @@ impl<'a> RRTStar<'a> {
         }
     }
 
-    fn is_collision_parent(&self, node: &RRTStarNode) -> bool {
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    pub(crate) fn is_collision_parent(&self, node: &RRTStarNode) -> bool {
         match node.node.parent_id {
             None => false,
             Some(parent_id) => {
@@ -139,6 +159,7 @@ impl<'a> RRTStar<'a> {
                     .get(parent_id as usize)
                     .expect("RRT Parent Node failed to get from node list");
                 self.is_collision_segment(&parent_node.node.point, &node.node.point)
+                    || self.is_collision_segment_timed(&parent_node.node.point, &node.node.point, parent_node.cost)
             }
         }
     }
@@ -152,11 +173,12 @@ impl<'a> RRTStar<'a> {
                     .get(parent_id as usize)
                     .expect("RRT Parent Node failed to get from node list");
                 self.is_collision_segment(&parent_node.node.point, &node.point)
+                    || self.is_collision_segment_timed(&parent_node.node.point, &node.point, parent_node.cost)
             }
         }
     }
 
-    fn find_near_nodes(&self, new_node: &RRTStarNode) -> Vec<usize> {
+    pub(crate) fn find_near_nodes(&self, new_node: &RRTStarNode) -> Vec<usize> {
         let n_nodes = (self.node_tree.len() + 1) as f32;
         let rm = self.connect_circle_dist * (n_nodes.log(f32::exp(1.0)) / n_nodes).sqrt();
         let r = if rm < self.rrt.expand_dis {
@@ -168,7 +190,7 @@ impl<'a> RRTStar<'a> {
         self.node_tree.get_within(&new_node, r)
     }
 
-    fn choose_parent(
+    pub(crate) fn choose_parent(
         &self,
         new_node: &RRTStarNode,
         near_inds: &Vec<usize>,
@@ -222,7 +244,11 @@ impl<'a> RRTStar<'a> {
         new_cost
     }
 
-    fn rewire(&mut self, new_node: &RRTStarNode, near_inds: &Vec<usize>) {
+    /// route near nodes through the new node when that is cheaper
+    ///
+    /// with moving obstacles the whole subtree below a rewired node arrives at new
+    /// times, so it is only rewired when all of its edges stay clear
+    pub(crate) fn rewire(&mut self, new_node: &RRTStarNode, near_inds: &Vec<usize>) {
         for idx in near_inds {
             //let mut near_node = self.node_list.get(*idx).unwrap();
             let (edge_node, edge_cost, improved_cost) = {
@@ -245,34 +271,73 @@ impl<'a> RRTStar<'a> {
                     cost: edge_cost,
                     node: RRTNode { id: *idx, parent_id:edge_node.parent_id, point: edge_node.point, path: edge_node.path },
                 };
-                self.node_tree.set(nnode);
-                self.propagate_cost_to_leaves(new_node);
+                if !self.dynamic_obstacles().is_empty()
+                    && (self.is_collision_segment_timed(
+                        &new_node.node.point,
+                        &nnode.node.point,
+                        new_node.cost,
+                    ) || self.is_collision_subtree_timed(&nnode))
+                {
+                    continue;
+                }
+                self.node_tree.set(nnode.clone());
+                self.propagate_cost_to_leaves(&nnode);
             }
         }
     }
 
-    fn propagate_cost_to_leaves(&mut self, new_node: &RRTStarNode) {
-        // FIXME: this is a mess
-        for idx in 0..self.node_tree.len() {
-            if self.node_tree.get(idx).unwrap().node.parent_id.is_none() {
-                continue;
-            }
-            if self.node_tree.get(idx).unwrap().node.parent_id.unwrap() == new_node.node.id {
-                let new_cost = self.calc_new_cost(&new_node, &self.node_tree.get(idx).unwrap());
-                let onode = self.node_tree.get(idx).unwrap();
+    /// do the moving obstacles hit an edge below the node, with the node at its new cost
+    fn is_collision_subtree_timed(&self, parent_node: &RRTStarNode) -> bool {
+        self.node_tree
+            .node_list()
+            .iter()
+            .filter(|n| n.node.parent_id == Some(parent_node.node.id))
+            .any(|onode| {
                 let nnode = RRTStarNode {
-                    cost: new_cost,
-                    node: onode.node.clone()
+                    cost: self.calc_new_cost(parent_node, onode),
+                    node: onode.node.clone(),
                 };
-                self.node_tree.set(nnode);
-                //let nnode = self.node_tree.get(idx).unwrap();
-                //let node = nnode.clone();
-                //self.propagate_cost_to_leaves(&node);
+                let (a, b) = (&parent_node.node.point, &onode.node.point);
+                self.is_collision_segment_timed(a, b, parent_node.cost)
+                    || self.is_collision_subtree_timed(&nnode)
+            })
+    }
+
+    fn propagate_cost_to_leaves(&mut self, parent_node: &RRTStarNode) {
+        let child_inds: Vec<usize> = self
+            .node_tree
+            .node_list()
+            .iter()
+            .filter(|n| n.node.parent_id == Some(parent_node.node.id))
+            .map(|n| n.node.id)
+            .collect();
+        for idx in child_inds {
+            let onode = self.node_tree.get(idx).unwrap();
+            let nnode = RRTStarNode {
+                cost: self.calc_new_cost(parent_node, onode),
+                node: onode.node.clone(),
+            };
+            self.node_tree.set(nnode.clone());
+            self.propagate_cost_to_leaves(&nnode);
+        }
+    }
+
+    pub(crate) fn search_best_goal_node(&self) -> Option<usize> {
+        let safe_goal_inds = self.safe_goal_inds();
+        let min_cost = safe_goal_inds
+            .iter()
+            .fold(f32::INFINITY, |a, &b| a.min(self.node_tree.get(b).unwrap().cost));
+        for idx in safe_goal_inds {
+            if self.node_tree.get(idx).unwrap().cost == min_cost {
+                return Some(idx);
             }
         }
+
+        None
     }
 
-    fn search_best_goal_node(&self) -> Option<usize> {
+    /// nodes within expand_dis of the goal whose step toward it is free
+    pub(crate) fn safe_goal_inds(&self) -> Vec<usize> {
         let goal_node = RRTStarNode {
             node: RRTNode::new(self.rrt.goal),
             cost: 0.0
@@ -291,17 +356,7 @@ impl<'a> RRTStar<'a> {
                 safe_goal_inds.push(idx);
             }
         }
-
-        let min_cost = safe_goal_inds
-            .iter()
-            .fold(f32::INFINITY, |a, &b| a.min(self.node_tree.get(b).unwrap().cost));
-        for idx in safe_goal_inds {
-            if self.node_tree.get(idx).unwrap().cost == min_cost {
-                return Some(idx);
-            }
-        }
-
-        None
+        safe_goal_inds
     }
 
     pub fn get_random_node(&mut self, end: &RRTNode) -> RRTStarNode {
@@ -311,3 +366,93 @@ impl<'a> RRTStar<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bound::CircleBounds;
+    use crate::dynamic::ConstantVelocityObstacle;
+
+    #[test]
+    fn seeded_plans_repeat() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let circle = CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 2.0,
+        };
+        let run = |seed| {
+            let mut rrtstar =
+                RRTStar::new((1.0, 1.0), (9.0, 9.0), vec![&circle], 0.5, 0.1, 5, 500, area, 5.0, true)
+                    .with_seed(seed);
+            let path = rrtstar.plan().map(|p| p.0);
+            let tree: Vec<(Point2D, Option<usize>, f32)> = rrtstar
+                .node_tree
+                .node_list()
+                .iter()
+                .map(|n| (n.node.point, n.node.parent_id, n.cost))
+                .collect();
+            (path, tree)
+        };
+
+        let (path, tree) = run(3);
+        assert!(path.is_some());
+        assert_eq!((path.clone(), tree.clone()), run(3));
+        assert_ne!(tree, run(4).1);
+
+        // pinned so that changes to the search show up
+        let length = |seed| Path2D(run(seed).0.unwrap()).path_length();
+        assert!((length(3) - 14.865274).abs() < 1e-4);
+        assert!((length(4) - 13.858909).abs() < 1e-4);
+    }
+
+    fn crossing_box(position: Point2D) -> ConstantVelocityObstacle<'static> {
+        let shape = RectangleBounds {
+            min_pt: (-1.0, -1.0),
+            max_pt: (1.0, 1.0),
+        };
+        ConstantVelocityObstacle::new(Box::new(shape), position, (0.0, -1.0))
+    }
+
+    #[test]
+    fn paths_dodge_moving_obstacles() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        // the box crosses the straight line while the robot would drive through it
+        let crossing = crossing_box((5.0, 9.0));
+        for seed in 0..5 {
+            let mut rrtstar =
+                RRTStar::new((1.0, 5.0), (9.0, 5.0), vec![], 0.5, 0.1, 5, 1000, area, 5.0, true)
+                    .with_seed(seed);
+            rrtstar.rrt.dynamic_obstacles = vec![&crossing];
+            assert!(rrtstar.is_collision_path_timed(&Path2D(vec![(9.0, 5.0), (1.0, 5.0)])));
+
+            let path = rrtstar.plan().expect("a path around the box");
+            assert!(!rrtstar.is_collision_path_timed(&path));
+        }
+    }
+
+    #[test]
+    fn rewires_with_moving_obstacles() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        // never near the tree, the search must not change
+        let far_away = crossing_box((50.0, 50.0));
+        let run = |moving: bool| {
+            let mut rrtstar =
+                RRTStar::new((1.0, 5.0), (9.0, 5.0), vec![], 0.5, 0.1, 5, 500, area, 5.0, true)
+                    .with_seed(1);
+            if moving {
+                rrtstar.rrt.dynamic_obstacles = vec![&far_away];
+            }
+            rrtstar.plan().map(|p| p.0)
+        };
+        assert_eq!(run(true), run(false));
+    }
+}
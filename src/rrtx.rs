@@ -0,0 +1,632 @@
+/// RRTx, replanning with a shortest-path tree rooted at the goal
+use crate::bound::*;
+use crate::dynamic::DynamicObstacle;
+use crate::footprint::Footprint;
+use crate::math::*;
+use crate::path::Path2D;
+use crate::planner::Planner;
+use crate::rrt::RRT;
+use crate::rrtnode::Node;
+use crate::rrtnode::RRTNode;
+use kiddo::{distance::squared_euclidean, KdTree};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// tolerance of g and lmc before a node counts as inconsistent
+const EPSILON: f32 = 1e-4;
+
+/// graph vertex, costs are cost-to-goal
+#[derive(Debug, Clone)]
+struct RRTxNode {
+    point: Point2D,
+    /// cost-to-goal through the tree
+    g: f32,
+    /// one step lookahead cost-to-goal through the best neighbour
+    lmc: f32,
+    /// next node toward the goal
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// edge length to every neighbour, infinite while the edge is blocked
+    neighbours: BTreeMap<usize, f32>,
+    in_queue: bool,
+}
+
+impl RRTxNode {
+    fn new(point: Point2D) -> Self {
+        Self {
+            point,
+            g: f32::INFINITY,
+            lmc: f32::INFINITY,
+            parent: None,
+            children: Vec::new(),
+            neighbours: BTreeMap::new(),
+            in_queue: false,
+        }
+    }
+
+    fn key(&self) -> (f32, f32) {
+        (self.g.min(self.lmc), self.g)
+    }
+}
+
+/// inconsistency queue entry, ordered so the BinaryHeap pops the lowest key
+struct QueueItem {
+    key: (f32, f32),
+    id: usize,
+}
+
+impl PartialEq for QueueItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for QueueItem {}
+
+impl PartialOrd for QueueItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .0
+            .total_cmp(&self.key.0)
+            .then(other.key.1.total_cmp(&self.key.1))
+    }
+}
+
+fn key_less(a: (f32, f32), b: (f32, f32)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+/// RRTx Configuration Object
+/// --
+///
+/// the tree grows from the goal and every node keeps its cost-to-goal, so the robot can
+/// move (`set_start`) and obstacles can be added or removed between calls to `plan` without
+/// starting over. Edges blocked by a new obstacle orphan only the subtree below them, which
+/// is then repaired by rewiring through the remaining graph.
+///
+/// `rrt.start` is the current robot position, `plan` grows the graph for `rrt.max_iter`
+/// more samples each call.
+///
+/// arrival times aren't known while the tree grows from the goal, so moving obstacles aren't
+/// supported: `set_dynamic_obstacles` and `plan` panic with any of them in `rrt.dynamic_obstacles`.
+pub struct RRTx<'a> {
+    pub rrt: RRT<'a>,
+    pub connect_circle_dist: f32,
+    nodes: Vec<RRTxNode>,
+    kd_tree: KdTree<f32, usize, 2>,
+    queue: BinaryHeap<QueueItem>,
+    /// node of the robot
+    bot: usize,
+}
+
+const GOAL: usize = 0;
+
+const NO_DYNAMIC_OBSTACLES: &str =
+    "RRTx doesn't support moving obstacles, use add_obstacle and remove_obstacle instead";
+
+impl Planner<'_> for RRTx<'_> {
+    fn obstacles(&self) -> &Vec<&dyn Collision> {
+        self.rrt.obstacles()
+    }
+
+    fn footprint(&self) -> Footprint {
+        self.rrt.footprint()
+    }
+
+    fn dynamic_obstacles(&self) -> &[&dyn DynamicObstacle] {
+        self.rrt.dynamic_obstacles()
+    }
+
+    fn nominal_speed(&self) -> f32 {
+        self.rrt.nominal_speed()
+    }
+
+    /// grow the graph for max_iter samples, then return the path from the goal to the robot
+    ///
+    /// panics if moving obstacles were put into `rrt.dynamic_obstacles`
+    fn plan(&mut self) -> Option<Path2D> {
+        assert!(self.dynamic_obstacles().is_empty(), "{}", NO_DYNAMIC_OBSTACLES);
+        for _idx in 1..=self.rrt.max_iter {
+            let rnd = self.rrt.get_random_node(&RRTNode::new(self.rrt.start)).point;
+            let nearest = match self.kd_tree.nearest_one(&[rnd.0, rnd.1], &squared_euclidean) {
+                Ok((_d, idx)) => *idx,
+                Err(_) => continue,
+            };
+
+            // saturate the sample to expand_dis from its nearest node
+            let from = self.nodes[nearest].point;
+            let d = euclidean_distance(&subtract(&rnd, &from));
+            // already in the graph (e.g. the goal biased sample at the robot)
+            if d < EPSILON {
+                continue;
+            }
+            let new_pt = if d > self.rrt.expand_dis {
+                let s = self.rrt.expand_dis / d;
+                (from.0 + (rnd.0 - from.0) * s, from.1 + (rnd.1 - from.1) * s)
+            } else {
+                rnd
+            };
+            if !self.rrt.explore_area.is_collision(&new_pt) || self.is_collision(&new_pt) {
+                continue;
+            }
+            if self.extend(new_pt, false).is_some() {
+                self.reduce_inconsistency();
+            }
+        }
+        self.path()
+    }
+}
+
+impl<'a> RRTx<'a> {
+    /// create new graph with the goal as root, leave other parameters open
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start: Point2D,
+        goal: Point2D,
+        obstacles: Vec<&'a dyn Collision>,
+        expand_dis: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        explore_area: RectangleBounds,
+        connect_circle_dist: f32,
+    ) -> Self {
+        let mut root = RRTxNode::new(goal);
+        root.g = 0.0;
+        root.lmc = 0.0;
+        let mut kd_tree = KdTree::new();
+        kd_tree.add(&[goal.0, goal.1], GOAL).expect("kd tree failed to add the goal");
+
+        let mut planner = Self {
+            rrt: RRT::new(
+                start,
+                goal,
+                obstacles,
+                expand_dis,
+                expand_dis,
+                goal_sample_rate,
+                max_iter,
+                explore_area,
+            ),
+            connect_circle_dist,
+            nodes: vec![root],
+            kd_tree,
+            queue: BinaryHeap::new(),
+            bot: GOAL,
+        };
+        planner.set_start(start);
+        planner
+    }
+
+    /// seed the random source, see `RRT::with_seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rrt = self.rrt.with_seed(seed);
+        self
+    }
+
+    /// moving obstacles aren't supported, panics unless the list is empty
+    pub fn set_dynamic_obstacles(&mut self, obstacles: Vec<&'a dyn DynamicObstacle>) {
+        assert!(obstacles.is_empty(), "{}", NO_DYNAMIC_OBSTACLES);
+        self.rrt.dynamic_obstacles = obstacles;
+    }
+
+    /// current path from the goal to the robot, following the tree
+    pub fn path(&self) -> Option<Path2D> {
+        if !self.nodes[self.bot].g.is_finite() {
+            return None;
+        }
+        let mut path = vec![self.nodes[self.bot].point];
+        let mut current = self.bot;
+        while let Some(parent) = self.nodes[current].parent {
+            path.push(self.nodes[parent].point);
+            current = parent;
+            // guard against a transient loop in the tree
+            if path.len() > self.nodes.len() {
+                return None;
+            }
+        }
+        if current != GOAL {
+            return None;
+        }
+        path.reverse();
+        Some(Path2D(path))
+    }
+
+    /// move the robot, its new position joins the graph and the tree is repaired toward it
+    ///
+    /// false (and nothing changes) if the position is in collision
+    pub fn set_start(&mut self, start: Point2D) -> bool {
+        if self.is_collision(&start) {
+            return false;
+        }
+        self.rrt.start = start;
+        let existing = match self.kd_tree.nearest_one(&[start.0, start.1], &squared_euclidean) {
+            Ok((d, idx)) if d < EPSILON * EPSILON => Some(*idx),
+            _ => None,
+        };
+        if let Some(id) = existing.or_else(|| self.extend(start, true)) {
+            self.bot = id;
+        }
+        self.reduce_inconsistency();
+        true
+    }
+
+    /// add an obstacle, the subtrees behind newly blocked edges are orphaned and repaired
+    pub fn add_obstacle(&mut self, obstacle: &'a dyn Collision) {
+        self.rrt.obstacles.push(obstacle);
+
+        let footprint = self.footprint();
+        let mut orphans = Vec::new();
+        for v in 0..self.nodes.len() {
+            let inside = footprint.is_collision(obstacle, &self.nodes[v].point);
+            let blocked: Vec<usize> = self.nodes[v]
+                .neighbours
+                .iter()
+                .filter(|(u, d)| {
+                    **u > v
+                        && d.is_finite()
+                        && (inside
+                            || footprint.is_collision(obstacle, &self.nodes[**u].point)
+                            || footprint.is_collision_segment(obstacle, &self.nodes[v].point, &self.nodes[**u].point))
+                })
+                .map(|(u, _d)| *u)
+                .collect();
+            for u in blocked {
+                self.set_edge(v, u, f32::INFINITY);
+                if self.nodes[v].parent == Some(u) {
+                    orphans.push(v);
+                }
+                if self.nodes[u].parent == Some(v) {
+                    orphans.push(u);
+                }
+            }
+        }
+
+        self.propagate_descendants(orphans);
+        self.reduce_inconsistency();
+    }
+
+    /// remove a previously given obstacle, edges it alone blocked are restored
+    ///
+    /// false if the obstacle isn't in the obstacle list
+    pub fn remove_obstacle(&mut self, obstacle: &dyn Collision) -> bool {
+        let target = obstacle as *const dyn Collision as *const ();
+        let pos = match self
+            .rrt
+            .obstacles
+            .iter()
+            .position(|o| *o as *const dyn Collision as *const () == target)
+        {
+            Some(p) => p,
+            None => return false,
+        };
+        let removed = self.rrt.obstacles.remove(pos);
+        let footprint = self.footprint();
+
+        for v in 0..self.nodes.len() {
+            let freed: Vec<usize> = self.nodes[v]
+                .neighbours
+                .iter()
+                .filter(|(u, d)| **u > v && !d.is_finite())
+                .map(|(u, _d)| *u)
+                .filter(|u| {
+                    let (a, b) = (&self.nodes[v].point, &self.nodes[*u].point);
+                    (footprint.is_collision(removed, a)
+                        || footprint.is_collision(removed, b)
+                        || footprint.is_collision_segment(removed, a, b))
+                        && !self.is_collision(a)
+                        && !self.is_collision(b)
+                        && !self.is_collision_segment(a, b)
+                })
+                .collect();
+            for u in freed {
+                let d = euclidean_distance(&subtract(&self.nodes[v].point, &self.nodes[u].point));
+                self.set_edge(v, u, d);
+                for x in [v, u] {
+                    self.update_lmc(x);
+                    if (self.nodes[x].g - self.nodes[x].lmc).abs() > EPSILON {
+                        self.verify_queue(x);
+                    }
+                }
+            }
+        }
+
+        self.reduce_inconsistency();
+        true
+    }
+
+    /// RRT* ball radius for the current graph size
+    fn near_radius(&self) -> f32 {
+        let n_nodes = (self.nodes.len() + 1) as f32;
+        let rm = self.connect_circle_dist * (n_nodes.ln() / n_nodes).sqrt();
+        rm.min(self.rrt.expand_dis)
+    }
+
+    /// add a node linked to its near nodes (blocked edges are kept for later removals)
+    ///
+    /// samples are only added when they can reach the goal. The robot is always added, linked
+    /// within expand_dis (the longest edge of the graph) so it reaches the node it drives to.
+    fn extend(&mut self, pt: Point2D, is_robot: bool) -> Option<usize> {
+        let r = if is_robot {
+            self.rrt.expand_dis
+        } else {
+            self.near_radius()
+        };
+        let mut near: Vec<usize> = match self.kd_tree.within_unsorted(&[pt.0, pt.1], r * r, &squared_euclidean) {
+            Ok(s) => s.iter().map(|(_d, idx)| **idx).collect(),
+            Err(_) => Vec::new(),
+        };
+        if near.is_empty() && is_robot {
+            if let Ok((_d, idx)) = self.kd_tree.nearest_one(&[pt.0, pt.1], &squared_euclidean) {
+                near.push(*idx);
+            }
+        }
+
+        let id = self.nodes.len();
+        let mut node = RRTxNode::new(pt);
+        for u in near {
+            let other = &self.nodes[u];
+            let d = if self.is_collision(&other.point) || self.is_collision_segment(&pt, &other.point) {
+                f32::INFINITY
+            } else {
+                euclidean_distance(&subtract(&pt, &other.point))
+            };
+            if d + other.lmc < node.lmc {
+                node.lmc = d + other.lmc;
+                node.parent = Some(u);
+            }
+            node.neighbours.insert(u, d);
+        }
+        if !is_robot && node.parent.is_none() {
+            return None;
+        }
+
+        if self.kd_tree.add(&[pt.0, pt.1], id).is_err() {
+            return None;
+        }
+        for (u, d) in node.neighbours.iter() {
+            self.nodes[*u].neighbours.insert(id, *d);
+        }
+        if let Some(parent) = node.parent {
+            self.nodes[parent].children.push(id);
+        }
+        self.nodes.push(node);
+        self.verify_queue(id);
+        Some(id)
+    }
+
+    fn set_edge(&mut self, a: usize, b: usize, d: f32) {
+        self.nodes[a].neighbours.insert(b, d);
+        self.nodes[b].neighbours.insert(a, d);
+    }
+
+    fn make_parent(&mut self, id: usize, parent: Option<usize>) {
+        if let Some(old) = self.nodes[id].parent {
+            self.nodes[old].children.retain(|c| *c != id);
+        }
+        if let Some(new) = parent {
+            self.nodes[new].children.push(id);
+        }
+        self.nodes[id].parent = parent;
+    }
+
+    fn verify_queue(&mut self, id: usize) {
+        self.nodes[id].in_queue = true;
+        self.queue.push(QueueItem {
+            key: self.nodes[id].key(),
+            id,
+        });
+    }
+
+    /// lowest queued node, skipping entries whose key went stale
+    fn queue_top(&mut self) -> Option<QueueItem> {
+        while let Some(item) = self.queue.pop() {
+            let node = &self.nodes[item.id];
+            if !node.in_queue {
+                continue;
+            }
+            if node.key() != item.key {
+                // lazy key update
+                self.queue.push(QueueItem {
+                    key: node.key(),
+                    id: item.id,
+                });
+                continue;
+            }
+            return Some(item);
+        }
+        None
+    }
+
+    /// best neighbour toward the goal, ignoring the children of the node
+    fn update_lmc(&mut self, id: usize) {
+        if id == GOAL {
+            return;
+        }
+        let best = self.nodes[id]
+            .neighbours
+            .iter()
+            .filter(|(u, d)| d.is_finite() && self.nodes[**u].parent != Some(id))
+            .map(|(u, d)| (*u, d + self.nodes[*u].lmc))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((u, lmc)) if lmc.is_finite() => {
+                self.nodes[id].lmc = lmc;
+                self.make_parent(id, Some(u));
+            }
+            _ => {
+                self.nodes[id].lmc = f32::INFINITY;
+                self.make_parent(id, None);
+            }
+        }
+    }
+
+    /// let the neighbours of a node whose cost dropped route through it
+    fn rewire_neighbours(&mut self, id: usize) {
+        if self.nodes[id].g - self.nodes[id].lmc <= EPSILON {
+            return;
+        }
+        let lmc = self.nodes[id].lmc;
+        let neighbours: Vec<(usize, f32)> = self.nodes[id]
+            .neighbours
+            .iter()
+            .filter(|(u, d)| d.is_finite() && Some(**u) != self.nodes[id].parent)
+            .map(|(u, d)| (*u, *d))
+            .collect();
+        for (u, d) in neighbours {
+            if u != GOAL && self.nodes[u].lmc > d + lmc {
+                self.nodes[u].lmc = d + lmc;
+                self.make_parent(u, Some(id));
+                if self.nodes[u].g - self.nodes[u].lmc > EPSILON {
+                    self.verify_queue(u);
+                }
+            }
+        }
+    }
+
+    /// process inconsistent nodes until the robot's cost-to-goal is settled
+    fn reduce_inconsistency(&mut self) {
+        while let Some(item) = self.queue_top() {
+            let bot = &self.nodes[self.bot];
+            let bot_settled = !key_less(item.key, bot.key())
+                && (bot.g - bot.lmc).abs() <= EPSILON
+                && bot.g.is_finite()
+                && !bot.in_queue;
+            if bot_settled {
+                self.queue.push(item);
+                break;
+            }
+
+            let id = item.id;
+            self.nodes[id].in_queue = false;
+            if self.nodes[id].g - self.nodes[id].lmc > EPSILON {
+                self.update_lmc(id);
+                self.rewire_neighbours(id);
+            }
+            self.nodes[id].g = self.nodes[id].lmc;
+        }
+    }
+
+    /// cut the orphans and everything below them off the tree
+    ///
+    /// their neighbours are queued so that the orphans get rewired through them
+    fn propagate_descendants(&mut self, orphans: Vec<usize>) {
+        let mut subtree = Vec::new();
+        let mut in_subtree = vec![false; self.nodes.len()];
+        let mut stack = orphans;
+        while let Some(id) = stack.pop() {
+            if in_subtree[id] {
+                continue;
+            }
+            in_subtree[id] = true;
+            subtree.push(id);
+            stack.extend(self.nodes[id].children.iter().copied());
+        }
+
+        for id in subtree.iter() {
+            let node = &self.nodes[*id];
+            let outside: Vec<usize> = node
+                .neighbours
+                .keys()
+                .copied()
+                .chain(node.parent)
+                .filter(|u| *u != GOAL && !in_subtree[*u])
+                .collect();
+            for u in outside {
+                self.nodes[u].g = f32::INFINITY;
+                self.verify_queue(u);
+            }
+        }
+        for id in subtree {
+            self.make_parent(id, None);
+            let node = &mut self.nodes[id];
+            node.g = f32::INFINITY;
+            node.lmc = f32::INFINITY;
+            node.in_queue = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::ConstantVelocityObstacle;
+
+    fn planner<'a>() -> RRTx<'a> {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        RRTx::new((1.0, 1.0), (9.0, 9.0), vec![], 1.0, 5, 2000, area, 5.0).with_seed(4)
+    }
+
+    /// circle on the middle of the path's middle edge
+    fn blocking_circle(path: &Path2D) -> CircleBounds {
+        let i = path.0.len() / 2;
+        let (a, b) = (path.0[i - 1], path.0[i]);
+        CircleBounds {
+            center_pt: ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0),
+            radius: 0.3,
+        }
+    }
+
+    #[test]
+    fn obstacles_are_avoided_and_forgotten() {
+        // outlives the planner holding it
+        let circle;
+        let mut rrtx = planner();
+        let path = rrtx.plan().expect("path in free space");
+        let cost = path.path_length();
+        circle = blocking_circle(&path);
+        assert!(path.0.windows(2).any(|w| circle.is_collision_segment(&w[0], &w[1])));
+
+        // repaired without further sampling
+        let n_nodes = rrtx.nodes.len();
+        rrtx.add_obstacle(&circle);
+        let detour = rrtx.path().expect("path around the new circle");
+        assert_eq!(rrtx.nodes.len(), n_nodes);
+        assert!(detour.0.windows(2).all(|w| !circle.is_collision_segment(&w[0], &w[1])));
+        assert!(detour.path_length() > cost);
+
+        assert!(rrtx.remove_obstacle(&circle));
+        let restored = rrtx.path().expect("path without the circle");
+        assert!((restored.path_length() - cost).abs() < 1e-3);
+        assert!(!rrtx.remove_obstacle(&circle));
+    }
+
+    #[test]
+    fn moving_the_start_reuses_the_tree() {
+        let mut rrtx = planner();
+        let path = rrtx.plan().expect("path in free space");
+        let n_nodes = rrtx.nodes.len();
+
+        // drive to the second node of the path, it is already in the tree
+        let next = path.0[path.0.len() - 2];
+        assert!(rrtx.set_start(next));
+        assert_eq!(rrtx.nodes.len(), n_nodes);
+        let rest = rrtx.path().expect("path from the new start");
+        assert_eq!(rest.0[..], path.0[..path.0.len() - 1]);
+
+        // a new position joins the graph as one more node
+        assert!(rrtx.set_start((next.0 + 0.1, next.1)));
+        assert_eq!(rrtx.nodes.len(), n_nodes + 1);
+        assert!(rrtx.path().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't support moving obstacles")]
+    fn moving_obstacles_are_rejected() {
+        let shape = CircleBounds {
+            center_pt: (0.0, 0.0),
+            radius: 1.0,
+        };
+        let moving = ConstantVelocityObstacle::new(Box::new(shape), (5.0, 5.0), (1.0, 0.0));
+        planner().set_dynamic_obstacles(vec![&moving]);
+    }
+}
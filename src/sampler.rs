@@ -0,0 +1,301 @@
+/// Samplers of the explore area used by the planners
+use crate::bound::{Collision, RectangleBounds};
+use crate::math::Point2D;
+use rand::distributions::Uniform;
+use rand::{Rng, RngCore};
+
+/// source of sample points for a planner
+pub trait Sampler {
+    /// next sample, `target` is the point goal biased samplers pull toward (usually the goal)
+    fn sample(&mut self, target: &Point2D, rng: &mut dyn RngCore) -> Point2D;
+}
+
+/// map a point of the unit square onto the area
+fn scale(area: &RectangleBounds, u: f64, v: f64) -> Point2D {
+    (
+        area.min_pt.0 + (u * (area.max_pt.0 - area.min_pt.0) as f64) as f32,
+        area.min_pt.1 + (v * (area.max_pt.1 - area.min_pt.1) as f64) as f32,
+    )
+}
+
+/// normally distributed offset with standard deviation sigma (Box-Muller)
+fn gaussian(rng: &mut dyn RngCore, sigma: f32) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen_range(0.0..1.0);
+    sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+fn in_collision(obstacles: &[&dyn Collision], pt: &Point2D) -> bool {
+    obstacles.iter().any(|obs| obs.is_collision(pt))
+}
+
+/// independent uniform samples over the area
+#[derive(Debug, Clone)]
+pub struct UniformSampler {
+    pub area: RectangleBounds,
+}
+
+impl UniformSampler {
+    pub fn new(area: RectangleBounds) -> Self {
+        Self { area }
+    }
+}
+
+impl Sampler for UniformSampler {
+    fn sample(&mut self, _target: &Point2D, rng: &mut dyn RngCore) -> Point2D {
+        let uniform_x = Uniform::new(self.area.min_pt.0, self.area.max_pt.0);
+        let uniform_y = Uniform::new(self.area.min_pt.1, self.area.max_pt.1);
+        (rng.sample(uniform_x), rng.sample(uniform_y))
+    }
+}
+
+/// Halton sequence in bases 2 and 3 over the area (deterministic, low discrepancy)
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    pub area: RectangleBounds,
+    index: u64,
+}
+
+impl HaltonSampler {
+    pub fn new(area: RectangleBounds) -> Self {
+        // the sequence starts at the corner, skip it
+        Self { area, index: 1 }
+    }
+}
+
+/// digits of i in the base, mirrored around the radix point
+fn radical_inverse(mut i: u64, base: u64) -> f64 {
+    let mut f = 1.0;
+    let mut r = 0.0;
+    while i > 0 {
+        f /= base as f64;
+        r += f * (i % base) as f64;
+        i /= base;
+    }
+    r
+}
+
+impl Sampler for HaltonSampler {
+    fn sample(&mut self, _target: &Point2D, _rng: &mut dyn RngCore) -> Point2D {
+        let pt = scale(&self.area, radical_inverse(self.index, 2), radical_inverse(self.index, 3));
+        self.index += 1;
+        pt
+    }
+}
+
+/// two dimensional Sobol sequence over the area (deterministic, low discrepancy)
+///
+/// the first dimension is the van der Corput sequence, the second one uses the primitive
+/// polynomial x + 1. Points are generated in Gray code order.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    pub area: RectangleBounds,
+    index: u32,
+    x: [u32; 2],
+    directions: [[u32; 32]; 2],
+}
+
+impl SobolSampler {
+    pub fn new(area: RectangleBounds) -> Self {
+        let mut directions = [[0u32; 32]; 2];
+        for k in 0..32 {
+            directions[0][k] = 1 << (31 - k);
+            directions[1][k] = if k == 0 {
+                1 << 31
+            } else {
+                directions[1][k - 1] ^ (directions[1][k - 1] >> 1)
+            };
+        }
+        Self {
+            area,
+            index: 0,
+            x: [0, 0],
+            directions,
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn sample(&mut self, _target: &Point2D, _rng: &mut dyn RngCore) -> Point2D {
+        // flip the direction number of the lowest zero bit of the index
+        let mut c = self.index.trailing_ones() as usize;
+        if c >= 32 {
+            // the sequence is exhausted, start over
+            self.index = 0;
+            self.x = [0, 0];
+            c = 0;
+        }
+        self.x[0] ^= self.directions[0][c];
+        self.x[1] ^= self.directions[1][c];
+        self.index += 1;
+
+        let denom = (1u64 << 32) as f64;
+        scale(&self.area, self.x[0] as f64 / denom, self.x[1] as f64 / denom)
+    }
+}
+
+/// wrapper returning the target goal_sample_rate percent of the time (a draw from 0..100 below it)
+pub struct GoalBiasedSampler<'a> {
+    pub inner: Box<dyn Sampler + 'a>,
+    pub goal_sample_rate: u32,
+}
+
+impl<'a> GoalBiasedSampler<'a> {
+    pub fn new(inner: impl Sampler + 'a, goal_sample_rate: u32) -> Self {
+        Self {
+            inner: Box::new(inner),
+            goal_sample_rate,
+        }
+    }
+}
+
+impl Sampler for GoalBiasedSampler<'_> {
+    fn sample(&mut self, target: &Point2D, rng: &mut dyn RngCore) -> Point2D {
+        if rng.gen_range(0..100) < self.goal_sample_rate {
+            *target
+        } else {
+            self.inner.sample(target, rng)
+        }
+    }
+}
+
+/// Gaussian obstacle boundary sampling
+/// --
+///
+/// a uniform sample and a normally distributed neighbour are drawn, and the free one is kept
+/// when exactly one of them is in collision. Samples concentrate within a few sigma of
+/// obstacle boundaries. Falls back to a uniform sample after max_attempts pairs.
+pub struct GaussianSampler<'a> {
+    pub area: RectangleBounds,
+    pub obstacles: Vec<&'a dyn Collision>,
+    pub sigma: f32,
+    pub max_attempts: u32,
+}
+
+impl<'a> GaussianSampler<'a> {
+    pub fn new(area: RectangleBounds, obstacles: Vec<&'a dyn Collision>, sigma: f32) -> Self {
+        Self {
+            area,
+            obstacles,
+            sigma,
+            max_attempts: 100,
+        }
+    }
+}
+
+impl Sampler for GaussianSampler<'_> {
+    fn sample(&mut self, target: &Point2D, rng: &mut dyn RngCore) -> Point2D {
+        let mut uniform = UniformSampler::new(self.area);
+        for _ in 0..self.max_attempts {
+            let q1 = uniform.sample(target, rng);
+            let q2 = (q1.0 + gaussian(rng, self.sigma), q1.1 + gaussian(rng, self.sigma));
+            match (in_collision(&self.obstacles, &q1), in_collision(&self.obstacles, &q2)) {
+                (false, true) => return q1,
+                (true, false) if self.area.is_collision(&q2) => return q2,
+                _ => continue,
+            }
+        }
+        uniform.sample(target, rng)
+    }
+}
+
+/// bridge test sampling
+/// --
+///
+/// two points in collision, a normally distributed distance apart, are drawn and their midpoint
+/// is kept when it is free, so samples land in gaps narrower than a few sigma. Falls back to
+/// a uniform sample after max_attempts bridges.
+pub struct BridgeSampler<'a> {
+    pub area: RectangleBounds,
+    pub obstacles: Vec<&'a dyn Collision>,
+    pub sigma: f32,
+    pub max_attempts: u32,
+}
+
+impl<'a> BridgeSampler<'a> {
+    pub fn new(area: RectangleBounds, obstacles: Vec<&'a dyn Collision>, sigma: f32) -> Self {
+        Self {
+            area,
+            obstacles,
+            sigma,
+            max_attempts: 100,
+        }
+    }
+}
+
+impl Sampler for BridgeSampler<'_> {
+    fn sample(&mut self, target: &Point2D, rng: &mut dyn RngCore) -> Point2D {
+        let mut uniform = UniformSampler::new(self.area);
+        for _ in 0..self.max_attempts {
+            let q1 = uniform.sample(target, rng);
+            if !in_collision(&self.obstacles, &q1) {
+                continue;
+            }
+            let q2 = (q1.0 + gaussian(rng, self.sigma), q1.1 + gaussian(rng, self.sigma));
+            if !in_collision(&self.obstacles, &q2) {
+                continue;
+            }
+            let mid = ((q1.0 + q2.0) / 2.0, (q1.1 + q2.1) / 2.0);
+            if !in_collision(&self.obstacles, &mid) {
+                return mid;
+            }
+        }
+        uniform.sample(target, rng)
+    }
+}
+
+/// mix of a (narrow passage) sampler and uniform samples over the area
+///
+/// ratio is the fraction of samples taken from the inner sampler
+pub struct MixedSampler<'a> {
+    pub inner: Box<dyn Sampler + 'a>,
+    pub uniform: UniformSampler,
+    pub ratio: f32,
+}
+
+impl<'a> MixedSampler<'a> {
+    pub fn new(inner: impl Sampler + 'a, area: RectangleBounds, ratio: f32) -> Self {
+        Self {
+            inner: Box::new(inner),
+            uniform: UniformSampler::new(area),
+            ratio,
+        }
+    }
+}
+
+impl Sampler for MixedSampler<'_> {
+    fn sample(&mut self, target: &Point2D, rng: &mut dyn RngCore) -> Point2D {
+        if rng.gen_range(0.0..1.0) < self.ratio {
+            self.inner.sample(target, rng)
+        } else {
+            self.uniform.sample(target, rng)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn goal_bias_is_the_rate() {
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+        let target = (9.0, 9.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut hits = |rate| {
+            let mut sampler = GoalBiasedSampler::new(UniformSampler::new(area), rate);
+            (0..10000)
+                .filter(|_| sampler.sample(&target, &mut rng) == target)
+                .count()
+        };
+        // a rate of 0 never picks the target, 100 always does
+        assert_eq!(hits(0), 0);
+        assert_eq!(hits(100), 10000);
+        let some = hits(5);
+        assert!(some > 400 && some < 600);
+    }
+}
@@ -0,0 +1,834 @@
+/// Scene files describing a planning problem, stored as JSON or TOML
+use crate::bitstar::BITStar;
+use crate::bound::*;
+use crate::csg::{Complement, Difference, Intersection, Union};
+use crate::dubinsrrt::{DubinsRRT, DubinsRRTStar};
+use crate::fmtstar::FMTStar;
+use crate::footprint::Footprint;
+use crate::informedrrtstar::InformedRRTStar;
+use crate::lqr::{LinearModel, LQR};
+use crate::lqrrrtstar::LQRRRTStar;
+use crate::math::Point2D;
+use crate::planner::Planner;
+use crate::prm::{Connection, PRM};
+use crate::reedsshepp::ReedsSheppRRTStar;
+use crate::rrt::RRT;
+use crate::rrtconnect::RRTConnect;
+use crate::rrtstar::RRTStar;
+use crate::rrtx::RRTx;
+use crate::world::ObstacleWorld;
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// version written by this library, older files are upgraded when the schema changes
+pub const SCENE_VERSION: u32 = 1;
+
+/// errors loading, saving or building a scene
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    TomlSer(toml::ser::Error),
+    /// the scene is readable but can't be built (unknown version, degenerate shape, ...)
+    Format(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "cannot access scene: {}", e),
+            SceneError::Json(e) => write!(f, "invalid json scene: {}", e),
+            SceneError::Toml(e) => write!(f, "invalid toml scene: {}", e),
+            SceneError::TomlSer(e) => write!(f, "cannot write toml scene: {}", e),
+            SceneError::Format(msg) => write!(f, "invalid scene: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneError::Json(e)
+    }
+}
+
+impl From<toml::de::Error> for SceneError {
+    fn from(e: toml::de::Error) -> Self {
+        SceneError::Toml(e)
+    }
+}
+
+impl From<toml::ser::Error> for SceneError {
+    fn from(e: toml::ser::Error) -> Self {
+        SceneError::TomlSer(e)
+    }
+}
+
+/// obstacle of a scene, tagged by `type`
+///
+/// shapes without derived state are stored as they are, the others by the arguments of their
+/// constructors and checked when the scene is built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SceneObstacle {
+    Circle(CircleBounds),
+    Rectangle(RectangleBounds),
+    OrientedRectangle(OrientedRectangleBounds),
+    Capsule(CapsuleBounds),
+    SegmentWall(SegmentWall),
+    ConvexPolygon {
+        points: Vec<Point2D>,
+    },
+    Polygon {
+        exterior: Vec<Point2D>,
+        #[serde(default)]
+        holes: Vec<Vec<Point2D>>,
+    },
+    TrackCorridor {
+        centreline: Vec<Point2D>,
+        left_widths: Vec<f32>,
+        right_widths: Vec<f32>,
+        #[serde(default)]
+        closed: bool,
+    },
+    Union {
+        children: Vec<SceneObstacle>,
+    },
+    Intersection {
+        children: Vec<SceneObstacle>,
+    },
+    Difference {
+        base: Box<SceneObstacle>,
+        cut: Box<SceneObstacle>,
+    },
+    Complement {
+        inner: Box<SceneObstacle>,
+    },
+}
+
+impl SceneObstacle {
+    pub fn build(&self) -> Result<Box<dyn Collision>, SceneError> {
+        let children = |c: &[SceneObstacle]| c.iter().map(|o| o.build()).collect::<Result<Vec<_>, _>>();
+        Ok(match self {
+            SceneObstacle::Circle(c) => Box::new(*c),
+            SceneObstacle::Rectangle(r) => Box::new(*r),
+            SceneObstacle::OrientedRectangle(r) => Box::new(*r),
+            SceneObstacle::Capsule(c) => Box::new(*c),
+            SceneObstacle::SegmentWall(w) => Box::new(*w),
+            SceneObstacle::ConvexPolygon { points } => Box::new(
+                Some(points)
+                    .filter(|p| p.len() >= 3)
+                    .and_then(|p| ConvexPolygonBounds::new_from_points(p))
+                    .ok_or_else(|| SceneError::Format("degenerate convex polygon".to_string()))?,
+            ),
+            SceneObstacle::Polygon { exterior, holes } => Box::new(
+                PolygonBounds::new_with_holes(exterior, holes)
+                    .ok_or_else(|| SceneError::Format("polygon ring with fewer than three points".to_string()))?,
+            ),
+            SceneObstacle::TrackCorridor {
+                centreline,
+                left_widths,
+                right_widths,
+                closed,
+            } => Box::new(
+                TrackCorridor::new(centreline, left_widths, right_widths, *closed)
+                    .ok_or_else(|| SceneError::Format("track widths don't match the centreline".to_string()))?,
+            ),
+            SceneObstacle::Union { children: c } => Box::new(Union::new(children(c)?)),
+            SceneObstacle::Intersection { children: c } => Box::new(Intersection::new(children(c)?)),
+            SceneObstacle::Difference { base, cut } => Box::new(Difference::new(base.build()?, cut.build()?)),
+            SceneObstacle::Complement { inner } => Box::new(Complement::new(inner.build()?)),
+        })
+    }
+}
+
+/// planner and its hyperparameters, tagged by `type`
+///
+/// the arguments of the planner constructors, minus what the scene itself describes
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PlannerConfig {
+    #[serde(rename = "rrt")]
+    RRT {
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+    },
+    #[serde(rename = "rrt_star")]
+    RRTStar {
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    },
+    #[serde(rename = "informed_rrt_star")]
+    InformedRRTStar {
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    },
+    #[serde(rename = "rrt_connect")]
+    RRTConnect {
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+    },
+    #[serde(rename = "rrtx")]
+    RRTx {
+        expand_dis: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+    },
+    #[serde(rename = "prm")]
+    PRM {
+        n_samples: usize,
+        connection: Connection,
+        lazy: bool,
+    },
+    #[serde(rename = "fmt_star")]
+    FMTStar {
+        n_samples: usize,
+        connect_circle_dist: f32,
+        expand_dis: f32,
+    },
+    #[serde(rename = "bit_star")]
+    BITStar {
+        batch_size: usize,
+        max_batches: usize,
+        connect_circle_dist: f32,
+    },
+    #[serde(rename = "dubins_rrt")]
+    DubinsRRT {
+        turning_radius: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+    },
+    #[serde(rename = "dubins_rrt_star")]
+    DubinsRRTStar {
+        turning_radius: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    },
+    #[serde(rename = "reeds_shepp_rrt_star")]
+    ReedsSheppRRTStar {
+        turning_radius: f32,
+        reverse_penalty: f32,
+        expand_dis: f32,
+        path_resolution: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    },
+    /// planar double integrator steered by LQR, `q` (4 entries) and `r` (2 entries) are the
+    /// diagonals of the weight matrices
+    #[serde(rename = "lqr_rrt_star")]
+    LQRRRTStar {
+        dt: f32,
+        q: Vec<f32>,
+        r: Vec<f32>,
+        max_time: f32,
+        expand_dis: f32,
+        goal_sample_rate: u32,
+        max_iter: u32,
+        connect_circle_dist: f32,
+        search_until_max: bool,
+    },
+}
+
+/// Planning Scene
+/// --
+///
+/// everything needed to set up a planner: the explore area, start and goal, the obstacles and
+/// the planner configuration. `start_yaw` and `goal_yaw` are only used by the car-like planners,
+/// `seed` makes the sampling reproducible.
+///
+/// Files ending in `.toml` are TOML, anything else is JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub version: u32,
+    pub explore_area: RectangleBounds,
+    pub start: Point2D,
+    pub goal: Point2D,
+    #[serde(default)]
+    pub start_yaw: f32,
+    #[serde(default)]
+    pub goal_yaw: f32,
+    #[serde(default)]
+    pub footprint: Footprint,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub obstacles: Vec<SceneObstacle>,
+    pub planner: PlannerConfig,
+}
+
+impl Scene {
+    /// scene of the current version without obstacles
+    pub fn new(explore_area: RectangleBounds, start: Point2D, goal: Point2D, planner: PlannerConfig) -> Self {
+        Self {
+            version: SCENE_VERSION,
+            explore_area,
+            start,
+            goal,
+            start_yaw: 0.0,
+            goal_yaw: 0.0,
+            footprint: Footprint::Point,
+            seed: None,
+            obstacles: Vec::new(),
+            planner,
+        }
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, SceneError> {
+        Self::checked(serde_json::from_str(s)?)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, SceneError> {
+        Self::checked(toml::from_str(s)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        if is_toml(path) {
+            Self::from_toml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let content = if is_toml(path) { self.to_toml()? } else { self.to_json()? };
+        Ok(fs::write(path, content)?)
+    }
+
+    fn checked(scene: Self) -> Result<Self, SceneError> {
+        if scene.version != SCENE_VERSION {
+            return Err(SceneError::Format(format!("unsupported version {}", scene.version)));
+        }
+        Ok(scene)
+    }
+
+    /// build every obstacle, they are borrowed by the planner
+    pub fn build_obstacles(&self) -> Result<Vec<Box<dyn Collision>>, SceneError> {
+        self.obstacles.iter().map(|o| o.build()).collect()
+    }
+
+    /// build every obstacle into a world, which culls the planner's queries by bounding boxes
+    pub fn build_world(&self) -> Result<ObstacleWorld<'static>, SceneError> {
+        Ok(ObstacleWorld::new(self.build_obstacles()?))
+    }
+
+    /// set up the configured planner over the world built by build_world
+    pub fn planner<'a>(&self, world: &'a ObstacleWorld) -> Result<Box<dyn Planner<'a> + 'a>, SceneError> {
+        let obs: Vec<&'a dyn Collision> = world.into();
+        let (start, goal, area) = (self.start, self.goal, self.explore_area);
+        let start_pose = (start.0, start.1, self.start_yaw);
+        let goal_pose = (goal.0, goal.1, self.goal_yaw);
+        let seed = self.seed;
+        let footprint = self.footprint;
+
+        Ok(match self.planner {
+            PlannerConfig::RRT {
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+            } => {
+                let mut p = RRT::new(start, goal, obs, expand_dis, path_resolution, goal_sample_rate, max_iter, area);
+                p.footprint = footprint;
+                Box::new(seeded(p, seed, RRT::with_seed))
+            }
+            PlannerConfig::RRTStar {
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+                search_until_max,
+            } => {
+                let mut p = RRTStar::new(
+                    start,
+                    goal,
+                    obs,
+                    expand_dis,
+                    path_resolution,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                    connect_circle_dist,
+                    search_until_max,
+                );
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, RRTStar::with_seed))
+            }
+            PlannerConfig::InformedRRTStar {
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+                search_until_max,
+            } => {
+                let mut p = InformedRRTStar::new(
+                    start,
+                    goal,
+                    obs,
+                    expand_dis,
+                    path_resolution,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                    connect_circle_dist,
+                    search_until_max,
+                );
+                p.rrtstar.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, InformedRRTStar::with_seed))
+            }
+            PlannerConfig::RRTConnect {
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+            } => {
+                let mut p =
+                    RRTConnect::new(start, goal, obs, expand_dis, path_resolution, goal_sample_rate, max_iter, area);
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, RRTConnect::with_seed))
+            }
+            PlannerConfig::RRTx {
+                expand_dis,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+            } => {
+                let mut p = RRTx::new(start, goal, obs, expand_dis, goal_sample_rate, max_iter, area, connect_circle_dist);
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, RRTx::with_seed))
+            }
+            PlannerConfig::PRM {
+                n_samples,
+                connection,
+                lazy,
+            } => {
+                let mut p = PRM::new(start, goal, obs, area, n_samples, connection, lazy);
+                p.footprint = footprint;
+                Box::new(seeded(p, seed, PRM::with_seed))
+            }
+            PlannerConfig::FMTStar {
+                n_samples,
+                connect_circle_dist,
+                expand_dis,
+            } => {
+                let mut p = FMTStar::new(start, goal, obs, area, n_samples, connect_circle_dist, expand_dis);
+                p.footprint = footprint;
+                Box::new(seeded(p, seed, FMTStar::with_seed))
+            }
+            PlannerConfig::BITStar {
+                batch_size,
+                max_batches,
+                connect_circle_dist,
+            } => {
+                let mut p = BITStar::new(start, goal, obs, area, batch_size, max_batches, connect_circle_dist);
+                p.footprint = footprint;
+                Box::new(seeded(p, seed, BITStar::with_seed))
+            }
+            PlannerConfig::DubinsRRT {
+                turning_radius,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+            } => {
+                let mut p = DubinsRRT::new(
+                    start_pose,
+                    goal_pose,
+                    obs,
+                    turning_radius,
+                    expand_dis,
+                    path_resolution,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                );
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, DubinsRRT::with_seed))
+            }
+            PlannerConfig::DubinsRRTStar {
+                turning_radius,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+                search_until_max,
+            } => {
+                let mut p = DubinsRRTStar::new(
+                    start_pose,
+                    goal_pose,
+                    obs,
+                    turning_radius,
+                    expand_dis,
+                    path_resolution,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                    connect_circle_dist,
+                    search_until_max,
+                );
+                p.dubins_rrt.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, DubinsRRTStar::with_seed))
+            }
+            PlannerConfig::ReedsSheppRRTStar {
+                turning_radius,
+                reverse_penalty,
+                expand_dis,
+                path_resolution,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+                search_until_max,
+            } => {
+                let mut p = ReedsSheppRRTStar::new(
+                    start_pose,
+                    goal_pose,
+                    obs,
+                    turning_radius,
+                    reverse_penalty,
+                    expand_dis,
+                    path_resolution,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                    connect_circle_dist,
+                    search_until_max,
+                );
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, ReedsSheppRRTStar::with_seed))
+            }
+            PlannerConfig::LQRRRTStar {
+                dt,
+                ref q,
+                ref r,
+                max_time,
+                expand_dis,
+                goal_sample_rate,
+                max_iter,
+                connect_circle_dist,
+                search_until_max,
+            } => {
+                if q.len() != 4 || r.len() != 2 {
+                    return Err(SceneError::Format("lqr weights need 4 state and 2 input entries".to_string()));
+                }
+                let model = LinearModel::double_integrator(dt);
+                let q = DMatrix::from_diagonal(&q.clone().into());
+                let r = DMatrix::from_diagonal(&r.clone().into());
+                let lqr = LQR::new(model, q, r)
+                    .ok_or_else(|| SceneError::Format("lqr weights don't give a controller".to_string()))?;
+                let mut p = LQRRRTStar::new(
+                    start,
+                    goal,
+                    obs,
+                    lqr,
+                    max_time,
+                    expand_dis,
+                    goal_sample_rate,
+                    max_iter,
+                    area,
+                    connect_circle_dist,
+                    search_until_max,
+                );
+                p.rrt.footprint = footprint;
+                Box::new(seeded(p, seed, LQRRRTStar::with_seed))
+            }
+        })
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("toml"))
+}
+
+fn seeded<P>(planner: P, seed: Option<u64>, with_seed: fn(P, u64) -> P) -> P {
+    match seed {
+        Some(s) => with_seed(planner, s),
+        None => planner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> RectangleBounds {
+        RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        }
+    }
+
+    fn rrt_config() -> PlannerConfig {
+        PlannerConfig::RRT {
+            expand_dis: 0.5,
+            path_resolution: 0.1,
+            goal_sample_rate: 5,
+            max_iter: 2000,
+        }
+    }
+
+    /// one obstacle of every kind, the combinators nested
+    fn every_obstacle() -> Vec<SceneObstacle> {
+        let square = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        let circle = SceneObstacle::Circle(CircleBounds {
+            center_pt: (5.0, 5.0),
+            radius: 1.0,
+        });
+        let rectangle = SceneObstacle::Rectangle(RectangleBounds {
+            min_pt: (1.0, 1.0),
+            max_pt: (2.0, 3.0),
+        });
+        vec![
+            circle.clone(),
+            rectangle.clone(),
+            SceneObstacle::OrientedRectangle(OrientedRectangleBounds {
+                center_pt: (7.0, 2.0),
+                half_extents: (1.0, 0.5),
+                yaw: 0.3,
+            }),
+            SceneObstacle::Capsule(CapsuleBounds {
+                start_pt: (1.0, 8.0),
+                end_pt: (3.0, 8.0),
+                radius: 0.4,
+            }),
+            SceneObstacle::SegmentWall(SegmentWall {
+                start_pt: (8.0, 6.0),
+                end_pt: (8.0, 9.0),
+            }),
+            SceneObstacle::ConvexPolygon {
+                points: square.clone(),
+            },
+            SceneObstacle::Polygon {
+                exterior: vec![(4.0, 0.0), (7.0, 0.0), (7.0, 3.0), (4.0, 3.0)],
+                holes: vec![vec![(5.0, 1.0), (6.0, 1.0), (6.0, 2.0), (5.0, 2.0)]],
+            },
+            SceneObstacle::TrackCorridor {
+                centreline: vec![(1.0, 5.0), (3.0, 5.0), (3.0, 7.0)],
+                left_widths: vec![0.5; 3],
+                right_widths: vec![0.5; 3],
+                closed: false,
+            },
+            SceneObstacle::Union {
+                children: vec![
+                    circle.clone(),
+                    SceneObstacle::Intersection {
+                        children: vec![
+                            rectangle.clone(),
+                            SceneObstacle::ConvexPolygon { points: square },
+                        ],
+                    },
+                ],
+            },
+            SceneObstacle::Difference {
+                base: Box::new(SceneObstacle::Union {
+                    children: vec![circle.clone(), rectangle],
+                }),
+                cut: Box::new(SceneObstacle::Complement {
+                    inner: Box::new(circle),
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn json_and_toml_round_trip() {
+        let mut scene = Scene::new(area(), (0.5, 0.5), (9.5, 9.5), rrt_config());
+        scene.start_yaw = 0.5;
+        scene.goal_yaw = -1.0;
+        scene.footprint = Footprint::Rectangle {
+            length: 0.4,
+            width: 0.2,
+        };
+        scene.seed = Some(7);
+        scene.obstacles = every_obstacle();
+        let json = scene.to_json().unwrap();
+
+        let from_json = Scene::from_json(&json).unwrap();
+        assert_eq!(from_json.to_json().unwrap(), json);
+        let from_toml = Scene::from_toml(&scene.to_toml().unwrap()).unwrap();
+        assert_eq!(from_toml.to_json().unwrap(), json);
+        assert_eq!(from_toml.build_obstacles().unwrap().len(), scene.obstacles.len());
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut scene = Scene::new(area(), (0.5, 0.5), (9.5, 9.5), rrt_config());
+        scene.version = SCENE_VERSION + 1;
+        let json = scene.to_json().unwrap();
+        let toml = scene.to_toml().unwrap();
+        assert!(matches!(Scene::from_json(&json), Err(SceneError::Format(_))));
+        assert!(matches!(Scene::from_toml(&toml), Err(SceneError::Format(_))));
+    }
+
+    #[test]
+    fn degenerate_shapes_are_rejected() {
+        let degenerate = [
+            SceneObstacle::ConvexPolygon {
+                points: vec![(0.0, 0.0), (1.0, 1.0)],
+            },
+            SceneObstacle::TrackCorridor {
+                centreline: vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)],
+                left_widths: vec![0.5; 2],
+                right_widths: vec![0.5; 3],
+                closed: false,
+            },
+        ];
+        for obstacle in degenerate {
+            let mut scene = Scene::new(area(), (0.5, 0.5), (9.5, 9.5), rrt_config());
+            // nested too, the error surfaces from the children
+            scene.obstacles = vec![SceneObstacle::Union {
+                children: vec![obstacle.clone()],
+            }];
+            assert!(matches!(scene.build_world(), Err(SceneError::Format(_))));
+            assert!(matches!(obstacle.build(), Err(SceneError::Format(_))));
+        }
+    }
+
+    #[test]
+    fn every_planner_is_built() {
+        let configs = [
+            rrt_config(),
+            PlannerConfig::RRTStar {
+                expand_dis: 0.5,
+                path_resolution: 0.1,
+                goal_sample_rate: 5,
+                max_iter: 2000,
+                connect_circle_dist: 5.0,
+                search_until_max: false,
+            },
+            PlannerConfig::InformedRRTStar {
+                expand_dis: 0.5,
+                path_resolution: 0.1,
+                goal_sample_rate: 5,
+                max_iter: 2000,
+                connect_circle_dist: 5.0,
+                search_until_max: false,
+            },
+            PlannerConfig::RRTConnect {
+                expand_dis: 0.5,
+                path_resolution: 0.1,
+                goal_sample_rate: 5,
+                max_iter: 2000,
+            },
+            PlannerConfig::RRTx {
+                expand_dis: 1.0,
+                goal_sample_rate: 5,
+                max_iter: 1000,
+                connect_circle_dist: 5.0,
+            },
+            PlannerConfig::PRM {
+                n_samples: 200,
+                connection: Connection::KNearest(8),
+                lazy: true,
+            },
+            PlannerConfig::FMTStar {
+                n_samples: 300,
+                connect_circle_dist: 20.0,
+                expand_dis: 3.0,
+            },
+            PlannerConfig::BITStar {
+                batch_size: 100,
+                max_batches: 3,
+                connect_circle_dist: 20.0,
+            },
+            PlannerConfig::DubinsRRT {
+                turning_radius: 1.0,
+                expand_dis: 1.0,
+                path_resolution: 0.1,
+                goal_sample_rate: 10,
+                max_iter: 3000,
+            },
+            PlannerConfig::DubinsRRTStar {
+                turning_radius: 1.0,
+                expand_dis: 1.0,
+                path_resolution: 0.1,
+                goal_sample_rate: 10,
+                max_iter: 3000,
+                connect_circle_dist: 10.0,
+                search_until_max: false,
+            },
+            PlannerConfig::ReedsSheppRRTStar {
+                turning_radius: 1.0,
+                reverse_penalty: 2.0,
+                expand_dis: 1.0,
+                path_resolution: 0.1,
+                goal_sample_rate: 10,
+                max_iter: 3000,
+                connect_circle_dist: 10.0,
+                search_until_max: false,
+            },
+            PlannerConfig::LQRRRTStar {
+                dt: 0.1,
+                q: vec![1.0; 4],
+                r: vec![1.0; 2],
+                max_time: 10.0,
+                expand_dis: 1.0,
+                goal_sample_rate: 10,
+                max_iter: 300,
+                connect_circle_dist: 20.0,
+                search_until_max: false,
+            },
+        ];
+        let disc = Footprint::Disc { radius: 0.1 };
+        for config in configs {
+            let name = format!("{:?}", config);
+            let mut scene = Scene::new(area(), (2.0, 2.0), (8.0, 8.0), config);
+            scene.footprint = disc;
+            scene.seed = Some(1);
+            scene.obstacles = vec![SceneObstacle::Circle(CircleBounds {
+                center_pt: (5.0, 5.0),
+                radius: 1.0,
+            })];
+            let world = scene.build_world().unwrap();
+            let mut planner = scene.planner(&world).unwrap();
+            assert_eq!(planner.obstacles().len(), 1, "{name}");
+            assert_eq!(planner.footprint(), disc, "{name}");
+            assert!(planner.plan().is_some(), "{name}");
+        }
+    }
+}
@@ -1,23 +1,23 @@
 
 use kiddo::{KdTree, distance::squared_euclidean};
 use crate::{math::Point2D, Node};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// branching path tree
 /// --
 ///
 /// a spatial data structure that stores branching paths
-/// (e.g. the ones used in RRT)
+/// (e.g. the ones used in RRT), iterated in node id order
 pub struct PathTree<T> where T: Node {
     kd_tree: KdTree<f32, usize, 2>,
-    pub b_map: HashMap<usize, T>
+    pub b_map: BTreeMap<usize, T>
 }
 
 impl<T> PathTree<T> where T: Node{
     pub fn new() -> Self {
         Self{
             kd_tree: KdTree::<f32, usize, 2>::new(),
-            b_map: HashMap::<usize, T>::new()
+            b_map: BTreeMap::<usize, T>::new()
         }
     }
 
@@ -55,7 +55,8 @@ impl<T> PathTree<T> where T: Node{
     /// indices of nodes within r-ball of a node
     pub fn get_within(&self, node: &T, radius: f32) -> Vec<usize> {
         let point = node.point();
-        let r = self.kd_tree.within_unsorted(&[point.0, point.1], radius, &squared_euclidean);
+        // the kd tree compares squared distances
+        let r = self.kd_tree.within_unsorted(&[point.0, point.1], radius * radius, &squared_euclidean);
         match r {
             Ok(s) => {
                 let v: Vec<usize> = s.iter().map(
@@ -69,16 +70,18 @@ impl<T> PathTree<T> where T: Node{
 
     /// set an existing node in the data structure
     pub fn set(&mut self, node: T) {
-        // TODO: error handling
         let id = node.id();
         let point = node.point();
-        //if self.b_map.contains_key(&id) {
-            //let old_id = self.b_map.get(&id).unwrap().id();
-            //self.kd_tree.remove(&[point.0, point.1], &old_id).expect("idx wasn't found in kd_tree, which we checked for");
-        //}
-        self.b_map.remove(&node.id());
-        self.b_map.insert(node.id(), node);
-        self.kd_tree.add(&[point.0, point.1], id).expect("unable to add point in kd_tree, which we checked for");
+        // only touch the kd tree when the node is new or has moved
+        let old_point = self.b_map.get(&id).map(|n| n.point());
+        if old_point != Some(point) {
+            if let Some(old) = old_point {
+                // the kd tree may hold several stale entries for a node that moved
+                let _ = self.kd_tree.remove(&[old.0, old.1], &id);
+            }
+            self.kd_tree.add(&[point.0, point.1], id).expect("unable to add point in kd_tree, which we checked for");
+        }
+        self.b_map.insert(id, node);
     }
 
     /// get a specific path from the branching paths
@@ -113,4 +116,26 @@ impl<T> PathTree<T> where T: Node{
         r
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RRTNode;
+
+    #[test]
+    fn get_within_uses_the_radius() {
+        let mut tree = PathTree::<RRTNode>::new();
+        for (id, x) in [0.0, 0.3, 0.6, 1.5, 3.0].iter().enumerate() {
+            let mut node = RRTNode::new((*x, 0.0));
+            node.id = id;
+            tree.add_node(node);
+        }
+        let mut near = tree.get_within(&RRTNode::new((0.0, 0.0)), 0.5);
+        near.sort();
+        assert_eq!(near, vec![0, 1]);
+        let mut near = tree.get_within(&RRTNode::new((0.0, 0.0)), 2.0);
+        near.sort();
+        assert_eq!(near, vec![0, 1, 2, 3]);
+    }
+}
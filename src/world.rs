@@ -0,0 +1,398 @@
+/// Obstacle container with a bounding volume hierarchy for broad phase culling
+use crate::bound::{Collision, RectangleBounds};
+use crate::math::{euclidean_distance, merge_intervals, Point2D};
+
+/// most obstacles in a leaf of the hierarchy
+const LEAF_SIZE: usize = 4;
+
+/// node of the bounding volume hierarchy, children are indices into the node list
+#[derive(Debug, Clone)]
+enum BvhNode {
+    Leaf {
+        bounds: RectangleBounds,
+        items: Vec<usize>,
+    },
+    Branch {
+        bounds: RectangleBounds,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &RectangleBounds {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// Obstacle World
+/// --
+///
+/// owns a list of obstacles and a bounding volume hierarchy over their bounding boxes, so point
+/// and segment queries only run the exact tests on obstacles whose boxes are hit. Obstacles
+/// without a bounding box (keep in regions) are checked on every query.
+///
+/// Planners take the world in place of their obstacle list (`(&world).into()`), it is their only
+/// obstacle so every query goes through the hierarchy.
+pub struct ObstacleWorld<'a> {
+    obstacles: Vec<Box<dyn Collision + 'a>>,
+    boxes: Vec<Option<RectangleBounds>>,
+    nodes: Vec<BvhNode>,
+    unbounded: Vec<usize>,
+}
+
+impl<'a> ObstacleWorld<'a> {
+    pub fn new(obstacles: Vec<Box<dyn Collision + 'a>>) -> Self {
+        let mut world = Self {
+            obstacles,
+            boxes: Vec::new(),
+            nodes: Vec::new(),
+            unbounded: Vec::new(),
+        };
+        world.rebuild();
+        world
+    }
+
+    /// add an obstacle, the hierarchy is rebuilt
+    pub fn push(&mut self, obstacle: Box<dyn Collision + 'a>) {
+        self.obstacles.push(obstacle);
+        self.rebuild();
+    }
+
+    pub fn len(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&(dyn Collision + 'a)> {
+        self.obstacles.get(index).map(|o| o.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(dyn Collision + 'a)> {
+        self.obstacles.iter().map(|o| o.as_ref())
+    }
+
+    /// bounding box of every obstacle, None for unbounded ones
+    pub fn bounding_boxes(&self) -> &[Option<RectangleBounds>] {
+        &self.boxes
+    }
+
+    /// recompute the bounding boxes and the hierarchy
+    pub fn rebuild(&mut self) {
+        self.boxes = self.obstacles.iter().map(|o| o.bounding_box()).collect();
+        self.unbounded = (0..self.boxes.len()).filter(|i| self.boxes[*i].is_none()).collect();
+        self.nodes = Vec::new();
+        let bounded: Vec<usize> = (0..self.boxes.len()).filter(|i| self.boxes[*i].is_some()).collect();
+        if !bounded.is_empty() {
+            self.build(bounded);
+        }
+    }
+
+    /// split on the median center along the longest axis, returns the node index
+    fn build(&mut self, mut items: Vec<usize>) -> usize {
+        let bounds = items
+            .iter()
+            .filter_map(|i| self.boxes[*i])
+            .reduce(|a, b| union(&a, &b))
+            .expect("bvh node without obstacles");
+        if items.len() <= LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf { bounds, items });
+            return self.nodes.len() - 1;
+        }
+
+        let axis_x = bounds.max_pt.0 - bounds.min_pt.0 >= bounds.max_pt.1 - bounds.min_pt.1;
+        let center = |i: &usize| {
+            let b = self.boxes[*i].expect("bvh over unbounded obstacle");
+            if axis_x {
+                b.min_pt.0 + b.max_pt.0
+            } else {
+                b.min_pt.1 + b.max_pt.1
+            }
+        };
+        items.sort_by(|a, b| center(a).total_cmp(&center(b)));
+        let right_items = items.split_off(items.len() / 2);
+
+        // reserve the slot so the root stays at index 0
+        let id = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            items: Vec::new(),
+        });
+        let left = self.build(items);
+        let right = self.build(right_items);
+        self.nodes[id] = BvhNode::Branch { bounds, left, right };
+        id
+    }
+
+    /// visit the obstacles whose boxes pass the test, and the unbounded ones, until visit is true
+    fn any_candidate<T, V>(&self, test: T, mut visit: V) -> bool
+    where
+        T: Fn(&RectangleBounds) -> bool,
+        V: FnMut(&dyn Collision) -> bool,
+    {
+        if self.unbounded.iter().any(|i| visit(self.obstacles[*i].as_ref())) {
+            return true;
+        }
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !test(node.bounds()) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { items, .. } => {
+                    for i in items {
+                        let b = self.boxes[*i].expect("bvh over unbounded obstacle");
+                        if test(&b) && visit(self.obstacles[*i].as_ref()) {
+                            return true;
+                        }
+                    }
+                }
+                BvhNode::Branch { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+        false
+    }
+
+    /// obstacle with the smallest signed distance to pt, with that distance
+    ///
+    /// boxes farther than the best distance so far are skipped, the distance to a box is a
+    /// lower bound of the distance to anything inside it
+    fn nearest(&self, pt: &Point2D) -> Option<(usize, f32)> {
+        let mut best: Option<(usize, f32)> = None;
+        let consider = |i: usize, best: &mut Option<(usize, f32)>| {
+            let d = self.obstacles[i].signed_distance(pt);
+            if best.is_none_or(|(_, bd)| d < bd) {
+                *best = Some((i, d));
+            }
+        };
+        for i in &self.unbounded {
+            consider(*i, &mut best);
+        }
+        let prune = |b: &RectangleBounds, best: &Option<(usize, f32)>| {
+            let d = box_distance(b, pt);
+            d > 0.0 && best.is_some_and(|(_, bd)| d >= bd)
+        };
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if prune(node.bounds(), &best) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { items, .. } => {
+                    for i in items {
+                        consider(*i, &mut best);
+                    }
+                }
+                BvhNode::Branch { left, right, .. } => {
+                    // descend into the closer child first
+                    let (dl, dr) = (
+                        box_distance(self.nodes[*left].bounds(), pt),
+                        box_distance(self.nodes[*right].bounds(), pt),
+                    );
+                    if dl <= dr {
+                        stack.push(*right);
+                        stack.push(*left);
+                    } else {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+fn union(a: &RectangleBounds, b: &RectangleBounds) -> RectangleBounds {
+    RectangleBounds {
+        min_pt: (a.min_pt.0.min(b.min_pt.0), a.min_pt.1.min(b.min_pt.1)),
+        max_pt: (a.max_pt.0.max(b.max_pt.0), a.max_pt.1.max(b.max_pt.1)),
+    }
+}
+
+fn grow(b: &RectangleBounds, r: f32) -> RectangleBounds {
+    RectangleBounds {
+        min_pt: (b.min_pt.0 - r, b.min_pt.1 - r),
+        max_pt: (b.max_pt.0 + r, b.max_pt.1 + r),
+    }
+}
+
+/// distance from pt to the closed box, zero inside
+fn box_distance(b: &RectangleBounds, pt: &Point2D) -> f32 {
+    let dx = (b.min_pt.0 - pt.0).max(pt.0 - b.max_pt.0).max(0.0);
+    let dy = (b.min_pt.1 - pt.1).max(pt.1 - b.max_pt.1).max(0.0);
+    euclidean_distance(&(dx, dy))
+}
+
+/// does the segment touch the closed box (slab test)
+fn segment_hits_box(b: &RectangleBounds, start: &Point2D, end: &Point2D) -> bool {
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    for (s, e, min, max) in [
+        (start.0, end.0, b.min_pt.0, b.max_pt.0),
+        (start.1, end.1, b.min_pt.1, b.max_pt.1),
+    ] {
+        let d = e - s;
+        if d == 0.0 {
+            if s < min || s > max {
+                return false;
+            }
+            continue;
+        }
+        let (ta, tb) = ((min - s) / d, (max - s) / d);
+        lo = lo.max(ta.min(tb));
+        hi = hi.min(ta.max(tb));
+    }
+    lo <= hi
+}
+
+/// union of all obstacles, every query is culled by the bounding volume hierarchy
+/// the world as a planner's obstacle list, holding only the world itself
+impl<'w, 'a: 'w> From<&'w ObstacleWorld<'a>> for Vec<&'w dyn Collision> {
+    fn from(world: &'w ObstacleWorld<'a>) -> Self {
+        vec![world]
+    }
+}
+
+impl Collision for ObstacleWorld<'_> {
+    fn is_collision(&self, pt: &Point2D) -> bool {
+        self.any_candidate(|b| box_distance(b, pt) == 0.0, |o| o.is_collision(pt))
+    }
+
+    fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+        self.any_candidate(
+            |b| segment_hits_box(b, start, end),
+            |o| o.is_collision_segment(start, end),
+        )
+    }
+
+    /// union of the intervals of the obstacles whose boxes the segment hits
+    fn segment_intervals(&self, start: &Point2D, end: &Point2D) -> Vec<(f32, f32)> {
+        let mut intervals = Vec::new();
+        self.any_candidate(
+            |b| segment_hits_box(b, start, end),
+            |o| {
+                intervals.extend(o.segment_intervals(start, end));
+                false
+            },
+        );
+        merge_intervals(intervals)
+    }
+
+    fn bounding_box(&self) -> Option<RectangleBounds> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|n| *n.bounds())
+    }
+
+    fn is_collision_radius(&self, pt: &Point2D, radius: f32) -> bool {
+        self.any_candidate(
+            |b| box_distance(b, pt) <= radius,
+            |o| o.is_collision_radius(pt, radius),
+        )
+    }
+
+    fn is_collision_segment_radius(&self, start: &Point2D, end: &Point2D, radius: f32) -> bool {
+        self.any_candidate(
+            |b| segment_hits_box(&grow(b, radius), start, end),
+            |o| o.is_collision_segment_radius(start, end, radius),
+        )
+    }
+
+    fn is_collision_convex_polygon(&self, corners: &[Point2D]) -> bool {
+        let poly_box = match RectangleBounds::from_points(corners) {
+            Some(b) => b,
+            None => return false,
+        };
+        self.any_candidate(
+            |b| {
+                b.min_pt.0 <= poly_box.max_pt.0
+                    && poly_box.min_pt.0 <= b.max_pt.0
+                    && b.min_pt.1 <= poly_box.max_pt.1
+                    && poly_box.min_pt.1 <= b.max_pt.1
+            },
+            |o| o.is_collision_convex_polygon(corners),
+        )
+    }
+
+    /// smallest signed distance over the obstacles, infinite for an empty world
+    fn signed_distance(&self, pt: &Point2D) -> f32 {
+        self.nearest(pt).map_or(f32::INFINITY, |(_, d)| d)
+    }
+
+    /// closest point of the nearest obstacle
+    fn closest_point(&self, pt: &Point2D) -> Point2D {
+        match self.nearest(pt) {
+            Some((i, _)) => self.obstacles[i].closest_point(pt),
+            None => *pt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bound::CircleBounds;
+    use crate::planner::Planner;
+    use crate::rrt::RRT;
+    use std::cell::Cell;
+
+    /// circle counting its exact point and segment checks
+    struct CountingCircle<'c> {
+        circle: CircleBounds,
+        checks: &'c Cell<usize>,
+    }
+
+    impl Collision for CountingCircle<'_> {
+        fn is_collision(&self, pt: &Point2D) -> bool {
+            self.checks.set(self.checks.get() + 1);
+            self.circle.is_collision(pt)
+        }
+
+        fn is_collision_segment(&self, start: &Point2D, end: &Point2D) -> bool {
+            self.checks.set(self.checks.get() + 1);
+            self.circle.is_collision_segment(start, end)
+        }
+
+        fn bounding_box(&self) -> Option<RectangleBounds> {
+            self.circle.bounding_box()
+        }
+    }
+
+    #[test]
+    fn planners_query_through_the_hierarchy() {
+        let (near_checks, far_checks) = (Cell::new(0), Cell::new(0));
+        let circle = |x: f32, checks| CountingCircle {
+            circle: CircleBounds {
+                center_pt: (x, 5.0),
+                radius: 1.0,
+            },
+            checks,
+        };
+        let world = ObstacleWorld::new(vec![
+            Box::new(circle(5.0, &near_checks)),
+            Box::new(circle(50.0, &far_checks)),
+        ]);
+        let area = RectangleBounds {
+            min_pt: (0.0, 0.0),
+            max_pt: (10.0, 10.0),
+        };
+
+        let mut rrt = RRT::new((1.0, 1.0), (9.0, 9.0), (&world).into(), 0.5, 0.1, 5, 2000, area)
+            .with_seed(0);
+        assert!(rrt.plan().is_some());
+        assert!(near_checks.get() > 0);
+        // the far circle's box is never hit from inside the explore area
+        assert_eq!(far_checks.get(), 0);
+    }
+}